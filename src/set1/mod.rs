@@ -1,10 +1,28 @@
 #[cfg(test)]
 mod tests {
     use crate::shared::aes::{ecb_decrypt, is_ecb};
-    use crate::shared::conversion::{base64_to_bytes, bytes_to_base64, bytes_to_hex, hex_to_bytes};
+    use crate::shared::classical::{
+        affine_encrypt, break_affine, break_caesar, break_columnar_with_rng,
+        break_substitution_with_rng, break_vigenere, caesar_encrypt, columnar_decrypt,
+        columnar_encrypt, keyword_order, letters, substitution_encrypt, vigenere_decrypt,
+        vigenere_encrypt,
+    };
+    use crate::shared::conversion::{
+        ascii85_to_bytes, base58_to_bytes, base58check_to_bytes, base64_to_bytes, bytes_to_ascii85,
+        bytes_to_base58, bytes_to_base58check, bytes_to_base64, bytes_to_hex, bytes_to_upper_hex,
+        bytes_to_z85, detect_encodings, hex_to_bytes, hexdump, z85_to_bytes, Alphabet, Base32,
+        Base64, DecodeError, Encoding, Padding,
+    };
+    use crate::shared::mersenne_twister::MersenneTwister;
+    use crate::shared::xor::Scorer;
     use crate::shared::xor::{
-        break_xor_with_key, frequency_analysis, hamming_distance, xor, xor_with_key,
+        autocorrelation, break_xor_with_key, break_xor_with_key_ranked,
+        english_index_of_coincidence, estimate_key_sizes, estimate_key_sizes_ioc,
+        frequency_analysis, frequency_analysis_ranked, friedman_test, hamming_distance,
+        shortest_period, xor, xor_with_key, ByteHistogram, ChiSquared, LetterFrequency, NGram,
+        NonPrintable,
     };
+    use rand::SeedableRng;
     use std::fs::{read_to_string, File};
     use std::io::{BufRead, BufReader};
 
@@ -40,8 +58,8 @@ mod tests {
         let ct =
             hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")
                 .unwrap();
-        let (_, _, pt) = frequency_analysis(&ct).unwrap();
-        assert_eq!(pt, b"Cooking MC's like a pound of bacon")
+        let (_, _, pt) = frequency_analysis(&ct, &LetterFrequency::new_english()).unwrap();
+        assert_eq!(pt, b"Cooking MC's like a pound of bacon");
     }
 
     #[test]
@@ -50,7 +68,10 @@ mod tests {
             BufReader::new(File::open("src/set1/challenge4.txt").unwrap())
                 .lines()
                 .filter_map(|line| line.ok())
-                .filter_map(|line| frequency_analysis(&hex_to_bytes(&line).unwrap()))
+                .filter_map(|line| frequency_analysis(
+                    &hex_to_bytes(&line).unwrap(),
+                    &LetterFrequency::new_english()
+                ))
                 .any(|(_, _, pt)| pt == b"Now that the party is jumping\n")
         );
     }
@@ -80,7 +101,7 @@ mod tests {
         )
         .unwrap();
         assert_eq!(
-            break_xor_with_key(&ct, 40).unwrap(),
+            break_xor_with_key(&ct, 40, &LetterFrequency::new_english()).unwrap(),
             b"Terminator X: Bring the noise"
         );
     }
@@ -111,4 +132,316 @@ mod tests {
                 .all(|line| line.starts_with("d880619740a8a19b7840a8a31c810a3d"))
         );
     }
+
+    #[test]
+    fn test_base64() {
+        let url_safe = Base64::new_url_safe();
+        assert_eq!(url_safe.encode(b"\xfb\xff\xbf\x00"), "-_-_AA");
+        assert_eq!(url_safe.decode("-_-_AA").unwrap(), b"\xfb\xff\xbf\x00");
+        assert_eq!(
+            url_safe.decode("-_-_AA=="),
+            Err(DecodeError::InvalidPadding(6))
+        );
+        let lenient = Base64::new(Alphabet::Standard, Padding::Optional, None, true);
+        assert_eq!(lenient.decode("SSdt\nIGs").unwrap(), b"I'm k");
+        assert_eq!(
+            lenient.decode("SSdt\nIG*").unwrap_err(),
+            DecodeError::InvalidCharacter(7)
+        );
+        assert_eq!(base64_to_bytes("SSdtIGs"), None);
+
+        let mime = Base64::new_mime();
+        let ct = mime
+            .decode(&read_to_string("src/set1/challenge6.txt").unwrap())
+            .unwrap();
+        assert_eq!(mime.decode(&mime.encode(&ct)).unwrap(), ct);
+        assert!(mime
+            .encode(&[0; 64])
+            .split("\r\n")
+            .map(str::len)
+            .eq([76, 12]));
+        let unwrapped = Base64::new(Alphabet::Standard, Padding::Required, Some(0), false);
+        assert_eq!(unwrapped.encode(&[0; 64]), bytes_to_base64(&[0; 64]));
+    }
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(bytes_to_upper_hex(b"\xbe\xef"), "BEEF");
+        assert_eq!(hex_to_bytes("BEEF").unwrap(), b"\xbe\xef");
+        assert_eq!(Base32::new_standard().encode(b"foobar"), "MZXW6YTBOI======");
+        assert_eq!(Base32::new_standard().decode("MZXW6YQ=").unwrap(), b"foob");
+        assert_eq!(Base32::new_hex().encode(b"foobar"), "CPNMUOJ1E8======");
+        assert_eq!(
+            Base32::new_hex().decode("CPN=====").unwrap_err(),
+            DecodeError::InvalidLength(8)
+        );
+        assert_eq!(
+            bytes_to_base58(b"\x00\x00Hello World!"),
+            "112NEpo7TZRRrLZSi2U"
+        );
+        assert_eq!(
+            base58_to_bytes("112NEpo7TZRRrLZSi2U").unwrap(),
+            b"\x00\x00Hello World!"
+        );
+        assert_eq!(
+            base58_to_bytes("0OIl"),
+            Err(DecodeError::InvalidCharacter(0))
+        );
+        let address = "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2";
+        let payload = base58check_to_bytes(address).unwrap();
+        assert_eq!(
+            bytes_to_hex(&payload),
+            "0077bff20c60e522dfaa3350c39b030a5d004e839a"
+        );
+        assert_eq!(bytes_to_base58check(&payload), address);
+        assert_eq!(
+            base58check_to_bytes("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN3"),
+            Err(DecodeError::InvalidChecksum)
+        );
+        assert_eq!(
+            bytes_to_ascii85(b"\x00\x00\x00\x00Man sure."),
+            "z9jqo^F*2M7/c"
+        );
+        assert_eq!(
+            ascii85_to_bytes("<~z9jqo^\nF*2M7/c~>").unwrap(),
+            b"\x00\x00\x00\x00Man sure."
+        );
+        assert_eq!(ascii85_to_bytes("<~~>").unwrap(), b"");
+        assert!(ascii85_to_bytes("<~>").is_err());
+        let z85 = bytes_to_z85(b"\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b").unwrap();
+        assert_eq!(z85, "HelloWorld");
+        assert_eq!(
+            z85_to_bytes(&z85).unwrap(),
+            b"\x86\x4f\xd2\x6f\xb5\x59\xf7\x5b"
+        );
+        assert_eq!(bytes_to_z85(b"abc"), None);
+    }
+
+    #[test]
+    fn test_detect_encodings() {
+        assert_eq!(detect_encodings("48656c6c6f")[0], Encoding::Hex);
+        assert_eq!(detect_encodings("48656C6C6F")[0], Encoding::UpperHex);
+        assert_eq!(detect_encodings("MZXW6YTBOI======")[0], Encoding::Base32);
+        assert_eq!(
+            detect_encodings("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2")[0],
+            Encoding::Base58
+        );
+        assert_eq!(
+            detect_encodings("SSdtIGtpbGxpbmcgeW91ciBicmFpbiBsaWtlIGEgcG9pc29ub3VzIG11c2hyb29t")[0],
+            Encoding::Base64
+        );
+        assert_eq!(detect_encodings("-_-_AA")[0], Encoding::Base64UrlSafe);
+        assert!(!detect_encodings("<~>").contains(&Encoding::Ascii85));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let ct = hex_to_bytes("00112233445566778899aabbccddeeff41414141414141414141414141414141")
+            .unwrap();
+        let mut ecb_ct = ct.clone();
+        ecb_ct.extend_from_slice(&ct[..16]);
+        ecb_ct.extend_from_slice(b"tail");
+        let dump = hexdump(&ecb_ct, 16);
+        let lines = dump.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            "00000010  41 41 41 41 41 41 41 41 41 41 41 41 41 41 41 41  |AAAAAAAAAAAAAAAA|"
+        );
+        assert!(lines[2].ends_with("| = block 0"));
+        assert!(lines[0].ends_with(" = block 0"));
+        assert_eq!(
+            lines[3],
+            "00000030  74 61 69 6c                                      |tail            |"
+        );
+        assert_eq!(hexdump(b"tail", 0), "00000000  74 61 69 6c  |tail|\n");
+        assert_eq!(hexdump(b"", 0), "");
+    }
+
+    #[test]
+    fn test_scorers() {
+        let ct =
+            hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")
+                .unwrap();
+        let pt = b"Cooking MC's like a pound of bacon";
+        assert_eq!(
+            frequency_analysis(&ct, &ChiSquared::new_english())
+                .unwrap()
+                .2,
+            pt
+        );
+        assert_eq!(
+            frequency_analysis(&ct, &NGram::new_english_bigrams())
+                .unwrap()
+                .2,
+            pt
+        );
+        assert_eq!(
+            frequency_analysis(&ct, &NGram::new_english_quadgrams())
+                .unwrap()
+                .2,
+            pt
+        );
+        assert_eq!(
+            frequency_analysis(&ct, &ByteHistogram::new_english())
+                .unwrap()
+                .2,
+            pt
+        );
+
+        let ct = base64_to_bytes(
+            &read_to_string("src/set1/challenge6.txt")
+                .unwrap()
+                .replace("\n", ""),
+        )
+        .unwrap();
+        assert_eq!(
+            break_xor_with_key(&ct, 40, &ChiSquared::new_english()).unwrap(),
+            b"Terminator X: Bring the noise"
+        );
+        assert_eq!(
+            break_xor_with_key(&ct, 40, &ByteHistogram::new_english()).unwrap(),
+            b"Terminator X: Bring the noise"
+        );
+
+        // A single stray byte shouldn't make the correct key unrecoverable.
+        let mut ct = b"Cooking MC's like a pound of bacon\x00".to_vec();
+        xor_with_key(&mut ct, b"X");
+        let strict = LetterFrequency::new_english();
+        assert_ne!(
+            frequency_analysis(&ct, &strict).map(|(_, k, _)| k),
+            Some(b'X')
+        );
+        let tolerant = LetterFrequency::new(-7.0, NonPrintable::Penalize(10.0));
+        assert_eq!(frequency_analysis(&ct, &tolerant).unwrap().1, b'X');
+        let tolerant = ChiSquared::new([1.0; 28], NonPrintable::Penalize(10.0));
+        assert!(tolerant.score(b"abc\x00").is_some());
+        let mut expected = [1.0; 28];
+        expected[27] = 0.0;
+        let no_punctuation = ChiSquared::new(expected, NonPrintable::Reject);
+        assert!(no_punctuation.score(b"abc").unwrap().is_finite());
+    }
+
+    #[test]
+    fn test_ranked_candidates() {
+        let ct =
+            hex_to_bytes("1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736")
+                .unwrap();
+        let pt = b"Cooking MC's like a pound of bacon";
+        let candidates = frequency_analysis_ranked(&ct, &LetterFrequency::new_english(), 3);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].2, pt);
+        assert!(candidates.windows(2).all(|w| w[0].0 >= w[1].0));
+        assert!(frequency_analysis_ranked(b"", &ChiSquared::new_english(), 5).is_empty());
+
+        let ct = base64_to_bytes(
+            &read_to_string("src/set1/challenge6.txt")
+                .unwrap()
+                .replace("\n", ""),
+        )
+        .unwrap();
+        let key_sizes = estimate_key_sizes(&ct, 40);
+        assert_eq!(key_sizes.len(), 39);
+        assert!(key_sizes.windows(2).all(|w| w[0].1 <= w[1].1));
+        let candidates = break_xor_with_key_ranked(&ct, 40, &ByteHistogram::new_english(), 3, 2);
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].key_size, 29);
+        assert_eq!(candidates[0].key, b"Terminator X: Bring the noise");
+        // Multiples of the real key size are collapsed into the real key size.
+        assert!(candidates
+            .iter()
+            .enumerate()
+            .all(|(i, c)| candidates[..i].iter().all(|c_| c_.key_size != c.key_size)));
+        assert!(candidates.iter().all(|c| key_sizes
+            .iter()
+            .find(|&&(key_size, _)| key_size == c.key_size)
+            .is_none_or(|&(_, distance)| distance == c.normalized_distance)));
+        assert!(candidates[0]
+            .alternatives
+            .iter()
+            .zip(&candidates[0].key)
+            .all(|(alternatives, &k)| alternatives.len() == 2 && alternatives[0].1 == k));
+    }
+
+    #[test]
+    fn test_key_size_estimators() {
+        let ct = base64_to_bytes(
+            &read_to_string("src/set1/challenge6.txt")
+                .unwrap()
+                .replace("\n", ""),
+        )
+        .unwrap();
+        assert_eq!(estimate_key_sizes(&ct[..5], 40).len(), 1);
+        assert!(estimate_key_sizes(b"abc", 40).is_empty());
+        // Only the first eight blocks are compared.
+        assert_eq!(estimate_key_sizes(&ct, 3), estimate_key_sizes(&ct[..24], 3));
+        assert_eq!(estimate_key_sizes_ioc(&ct, 40)[0].0, 29);
+        assert_eq!(autocorrelation(&ct, 40)[0].0, 29);
+        let friedman = friedman_test(&ct, english_index_of_coincidence(), 1.0 / 256.0).unwrap();
+        assert!(friedman > 1.0);
+        assert_eq!(shortest_period(b"ICEICEICE"), 3);
+        assert_eq!(shortest_period(b"ICEICEIC"), 8);
+        let mut repeated = b"Terminator X: Bring the noise".repeat(2);
+        assert_eq!(shortest_period(&repeated), 29);
+        repeated.push(0);
+        assert_eq!(shortest_period(&repeated), 59);
+
+        let mut pt = ct.clone();
+        xor_with_key(&mut pt, b"Terminator X: Bring the noise");
+        let pt = &pt[..800];
+        let mut ice_ct = pt.to_vec();
+        xor_with_key(&mut ice_ct, b"ICE");
+        let candidates =
+            break_xor_with_key_ranked(&ice_ct, 12, &LetterFrequency::new_english(), 1, 1);
+        assert_eq!(candidates[0].key, b"ICE");
+        assert_eq!(
+            estimate_key_sizes(&ice_ct, 12)
+                .into_iter()
+                .find(|&(key_size, _)| key_size == 3),
+            Some((3, candidates[0].normalized_distance))
+        );
+    }
+
+    #[test]
+    fn test_classical_ciphers() {
+        let ct = base64_to_bytes(
+            &read_to_string("src/set1/challenge6.txt")
+                .unwrap()
+                .replace("\n", ""),
+        )
+        .unwrap();
+        // Repeating-key XOR is Vigenère over bytes, so the same tools break the classical ciphers.
+        let mut pt = ct.clone();
+        xor_with_key(&mut pt, b"Terminator X: Bring the noise");
+        let pt = &pt[..800];
+        let (shift, pt_) = break_caesar(&caesar_encrypt(pt, 11));
+        assert_eq!((shift, pt_.as_slice()), (11, pt));
+        let (key, pt_) = break_affine(&affine_encrypt(pt, 7, 3));
+        assert_eq!((key, pt_.as_slice()), ((7, 3), pt));
+        assert_eq!(break_caesar(b"1234"), (0, b"1234".to_vec()));
+        assert_eq!(break_affine(b"1234"), ((1, 0), b"1234".to_vec()));
+        let ct_ = vigenere_encrypt(pt, b"Lemon");
+        assert_eq!(vigenere_decrypt(&ct_, b"LEMON"), pt);
+        let (key, pt_) = break_vigenere(&ct_, 20).unwrap();
+        assert_eq!((key.as_slice(), pt_.as_slice()), (b"lemon".as_slice(), pt));
+        let key = *b"qwertyuiopasdfghjklzxcvbnm";
+        let ct_ = substitution_encrypt(pt, &key);
+        // The n-gram model can't tell rare letters apart perfectly, so only expect most of them to be right.
+        let mut rng = MersenneTwister::seed_from_u64(6);
+        let (_, pt_) =
+            break_substitution_with_rng(&ct_, &NGram::new_english_letter_quadgrams(), 30, &mut rng);
+        let correct = letters(pt)
+            .iter()
+            .zip(letters(&pt_))
+            .filter(|(a, b)| *a == b)
+            .count();
+        assert!(correct * 10 >= letters(pt).len() * 9);
+        let order = keyword_order(b"zebras");
+        assert_eq!(order, [4, 2, 1, 3, 5, 0]);
+        let ct_ = columnar_encrypt(pt, &order);
+        assert_eq!(columnar_decrypt(&ct_, &order), pt);
+        let (order_, pt_) =
+            break_columnar_with_rng(&ct_, 8, &NGram::new_english_quadgrams(), 20, &mut rng);
+        assert_eq!((order_, pt_.as_slice()), (order, pt));
+    }
 }
@@ -1,8 +1,8 @@
 #[cfg(test)]
 mod tests {
     use crate::shared::aes::{cbc_decrypt, cbc_encrypt, ecb_decrypt, ecb_encrypt, is_ecb};
-    use crate::shared::conversion::base64_to_bytes;
-    use crate::shared::key_value::parse_key_value;
+    use crate::shared::conversion::{hexdiff, Base64};
+    use crate::shared::key_value::{Codec, Error, Escaping, KeyValues};
     use crate::shared::padding::{pad_pkcs7, unpad_pkcs7};
     use crate::shared::xor::xor;
     use rand::{Rng, RngCore};
@@ -18,12 +18,9 @@ mod tests {
     fn test_challenge_10() {
         let key = b"YELLOW SUBMARINE";
         let iv = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
-        let ct = base64_to_bytes(
-            &read_to_string("src/set2/challenge10.txt")
-                .unwrap()
-                .replace("\n", ""),
-        )
-        .unwrap();
+        let ct = Base64::new_mime()
+            .decode(&read_to_string("src/set2/challenge10.txt").unwrap())
+            .unwrap();
         let mut pt = vec![0; ct.len()];
         cbc_decrypt(key, iv, &ct, &mut pt);
         assert!(String::from_utf8(pt)
@@ -64,12 +61,9 @@ mod tests {
 
     #[test]
     fn test_challenge_12() {
-        let unknown = base64_to_bytes(
-            &read_to_string("src/set2/challenge12.txt")
-                .unwrap()
-                .replace("\n", ""),
-        )
-        .unwrap();
+        let unknown = Base64::new_mime()
+            .decode(&read_to_string("src/set2/challenge12.txt").unwrap())
+            .unwrap();
         let mut rng = rand::thread_rng();
         let mut key = [0; 16];
         rng.fill_bytes(&mut key);
@@ -125,11 +119,25 @@ mod tests {
 
     #[test]
     fn test_challenge_13() {
-        let map = parse_key_value("foo=bar&baz=qux&zap=zazzle");
+        let codec = Codec::new_query();
+        let map = codec.decode("foo=bar&baz=qux&zap=zazzle").unwrap();
         assert_eq!(map.len(), 3);
         assert_eq!(map.get("foo").unwrap(), "bar");
         assert_eq!(map.get("baz").unwrap(), "qux");
         assert_eq!(map.get("zap").unwrap(), "zazzle");
+        assert_eq!(codec.encode(&map).unwrap(), "foo=bar&baz=qux&zap=zazzle");
+
+        let profile_for = |email: &str| {
+            let map: KeyValues = [("email", email), ("uid", "10"), ("role", "user")]
+                .into_iter()
+                .collect();
+            codec.encode(&map).unwrap()
+        };
+        let profile = profile_for("foo@bar.com&role=admin");
+        assert_eq!(profile, "email=foo@bar.com%26role%3Dadmin&uid=10&role=user");
+        let map = codec.decode(&profile).unwrap();
+        assert_eq!(map.get("email").unwrap(), "foo@bar.com&role=admin");
+        assert_eq!(map.get_all("role"), vec!["user"]);
 
         let mut rng = rand::thread_rng();
         let mut key = [0; 16];
@@ -144,13 +152,13 @@ mod tests {
             let mut pt = vec![0; ct.len()];
             ecb_decrypt(&key, ct, &mut pt);
             let pt = unpad_pkcs7(&pt, 16).unwrap();
-            parse_key_value(&String::from_utf8(pt).unwrap())
+            codec.decode(&String::from_utf8(pt).unwrap()).unwrap()
         };
 
         let ct1 = encrypt(
-            b"email=AAAAAAAAAAadmin\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b&uid=10&role=user",
+            profile_for("AAAAAAAAAAadmin\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b\x0b").as_bytes(),
         );
-        let ct2 = encrypt(b"email=evil@evil.com&uid=10&role=user");
+        let ct2 = encrypt(profile_for("evil@evil.com").as_bytes());
         let ct = [&ct2[0..16], &ct2[16..32], &ct1[16..32]].concat();
         let map = decrypt(&ct);
         assert_eq!(map.len(), 3);
@@ -161,12 +169,9 @@ mod tests {
 
     #[test]
     fn test_challenge_14() {
-        let unknown = base64_to_bytes(
-            &read_to_string("src/set2/challenge12.txt")
-                .unwrap()
-                .replace("\n", ""),
-        )
-        .unwrap();
+        let unknown = Base64::new_mime()
+            .decode(&read_to_string("src/set2/challenge12.txt").unwrap())
+            .unwrap();
         let mut rng = rand::thread_rng();
         let mut key = [0; 16];
         rng.fill_bytes(&mut key);
@@ -240,36 +245,90 @@ mod tests {
 
     #[test]
     fn test_challenge_16() {
-        let prefix = b"comment1=cooking%20MCs;userdata=";
-        let suffix = b";comment2=%20like%20a%20pound%20of%20bacon";
+        let codec = Codec::new_cookie();
         let mut rng = rand::thread_rng();
         let mut key = [0; 16];
         rng.fill_bytes(&mut key);
         let mut iv = [0; 16];
         rng.fill_bytes(&mut iv);
-        let encrypt = |pt: &[u8]| {
-            if pt.contains(&b';') || pt.contains(&b'=') {
-                return None;
-            }
-
-            let pt = pad_pkcs7(&[prefix, pt, suffix].concat(), 16);
+        let encrypt = |userdata: &str| {
+            let map: KeyValues = [
+                ("comment1", "cooking MCs"),
+                ("userdata", userdata),
+                ("comment2", " like a pound of bacon"),
+            ]
+            .into_iter()
+            .collect();
+            let pt = pad_pkcs7(codec.encode(&map).unwrap().as_bytes(), 16);
             let mut ct = vec![0; pt.len()];
             cbc_encrypt(&key, &iv, &pt, &mut ct);
-            Some(ct)
+            ct
         };
         let decrypt = |ct: &[u8]| {
             let mut pt = vec![0; ct.len()];
             cbc_decrypt(&key, &iv, &ct, &mut pt);
             let pt = unpad_pkcs7(&pt, 16).unwrap();
             // We need from_utf8_lossy here because the second block will be scrambled.
-            String::from_utf8_lossy(&pt).contains(";admin=true;")
+            let map = codec.decode(&String::from_utf8_lossy(&pt)).unwrap();
+            map.get("admin") == Some("true")
         };
 
-        let mut ct = encrypt(b"?admin?true").unwrap();
+        // The encoder escapes metacharacters, so we can't inject them directly.
+        assert!(!decrypt(&encrypt(";admin=true;")));
+
+        let mut ct = encrypt("?admin?true");
         let actual_pt = b"?admin?true;comm";
         let target_pt = b";admin=true;comm";
+        let original_ct = ct.clone();
         xor(&mut ct[16..16 + actual_pt.len()], actual_pt);
         xor(&mut ct[16..16 + target_pt.len()], target_pt);
         assert!(decrypt(&ct));
+
+        // Only the bytes at the positions of the injected metacharacters are flipped.
+        let diff = hexdiff(&original_ct, &ct, 16);
+        let lines = diff.lines().collect::<Vec<&str>>();
+        assert!(lines[1].starts_with("00000010  - "));
+        assert_eq!(
+            lines[3],
+            "            ^^                ^^                              ^     ^"
+        );
+        assert_eq!(lines.len(), ct.len() / 16 + 2);
+        assert_eq!(hexdiff(&original_ct, &ct, 0).lines().count(), 3);
+    }
+
+    #[test]
+    fn test_key_value_codec() {
+        let codec = Codec::new(';', '=', Escaping::Quote, false);
+        assert_eq!(codec.escape("plain").unwrap(), "plain");
+        assert_eq!(codec.escape("a;b").unwrap(), r#""a;b""#);
+        assert_eq!(codec.escape(r#"say "hi""#).unwrap(), r#""say \"hi\"""#);
+        let map: KeyValues = [("comment", r#"a;b="c"\"#), ("user", "bob")]
+            .into_iter()
+            .collect();
+        let encoded = codec.encode(&map).unwrap();
+        assert_eq!(encoded, r#"comment="a;b=\"c\"\\";user=bob"#);
+        assert_eq!(codec.decode(&encoded).unwrap(), map);
+        assert_eq!(
+            codec.decode(r#"k="unterminated"#),
+            Err(Error::InvalidEscape(2))
+        );
+        assert_eq!(codec.decode(r#"k="a\"#), Err(Error::InvalidEscape(4)));
+
+        let strict = Codec::new(';', '=', Escaping::Quote, true);
+        assert_eq!(strict.decode(r#"k="v"x;"#), Err(Error::Metacharacter(5)));
+        assert_eq!(strict.escape("a;b"), Err(Error::Metacharacter(1)));
+
+        let strict = Codec::new('&', '=', Escaping::Percent, true);
+        assert_eq!(strict.decode("foo"), Err(Error::MissingSeparator(0)));
+        assert_eq!(strict.decode("a=1&b"), Err(Error::MissingSeparator(4)));
+        assert_eq!(strict.decode("a=%zz"), Err(Error::InvalidEscape(2)));
+        assert_eq!(strict.decode("a=b=c"), Err(Error::Metacharacter(3)));
+        assert_eq!(
+            strict.encode(&[("a", "1&b=2")].into_iter().collect()),
+            Err(Error::Metacharacter(1))
+        );
+
+        let unescaped = Codec::new('&', '=', Escaping::None, false);
+        assert_eq!(unescaped.escape("a=b"), Err(Error::Metacharacter(1)));
     }
 }
@@ -1,11 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use crate::shared::aes::{cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt};
+    use crate::shared::aes::{cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_encrypt, ofb_encrypt};
     use crate::shared::conversion::base64_to_bytes;
-    use crate::shared::mersenne_twister::{clone_mt19937, encrypt, MersenneTwister};
+    use crate::shared::crib_drag::drag;
+    use crate::shared::keystream::{self, Keystream};
+    use crate::shared::mersenne_twister::{
+        brute_force_seeds, clone_mt19937, clone_mt19937_64, encrypt, recover_state,
+        MersenneTwister, MersenneTwister64, RecoveryError,
+    };
     use crate::shared::padding::{pad_pkcs7, unpad_pkcs7};
-    use crate::shared::xor::{break_xor_with_key, xor};
-    use rand::{Rng, RngCore};
+    use crate::shared::xor::{break_xor_with_key, xor, ByteHistogram, LetterFrequency};
+    use rand::{Rng, RngCore, SeedableRng};
     use std::fs::File;
     use std::io::{BufRead, BufReader};
 
@@ -119,7 +124,7 @@ mod tests {
                 accum
             });
 
-        let key_ = break_xor_with_key(&ct, min_length).unwrap();
+        let key_ = break_xor_with_key(&ct, min_length, &LetterFrequency::new_english()).unwrap();
         let mut pt = cts[0][0..min_length].to_owned();
         xor(&mut pt, &key_);
         // We ignore case here because "I have met them at c" is as likely as "i have met them at c"
@@ -157,7 +162,7 @@ mod tests {
                 accum
             });
 
-        let key_ = break_xor_with_key(&ct, min_length).unwrap();
+        let key_ = break_xor_with_key(&ct, min_length, &LetterFrequency::new_english()).unwrap();
         let mut pt = cts[0][0..min_length].to_owned();
         xor(&mut pt, &key_);
         assert_eq!(
@@ -231,4 +236,284 @@ mod tests {
             ct_[prefix_len..] == ct[prefix_len..]
         }));
     }
+
+    #[test]
+    fn test_keystream_recovery() {
+        let mut rng = rand::thread_rng();
+        let mut key = [0; 16];
+        rng.fill_bytes(&mut key);
+        let nonce = 0;
+        let pts: Vec<Vec<u8>> = BufReader::new(File::open("src/set3/challenge20.txt").unwrap())
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| base64_to_bytes(&line))
+            .collect();
+        let cts: Vec<Vec<u8>> = pts
+            .iter()
+            .map(|pt| {
+                let mut ct = vec![0; pt.len()];
+                ctr_encrypt(&key, nonce, pt, &mut ct);
+                ct
+            })
+            .collect();
+
+        // Unlike challenge 20, this uses every ciphertext that is long enough for each keystream byte.
+        let accuracy = |cts: &[Vec<u8>], keystream: &keystream::Keystream| {
+            let correct = cts
+                .iter()
+                .zip(&pts)
+                .map(|(ct, pt)| {
+                    let pt_ = keystream.decrypt(ct);
+                    pt_.iter().zip(pt).filter(|(a, b)| a == b).count()
+                })
+                .sum::<usize>();
+            f64::from(u32::try_from(correct).unwrap())
+                / f64::from(u32::try_from(pts.iter().map(Vec::len).sum::<usize>()).unwrap())
+        };
+        let scorer = ByteHistogram::new_english();
+        let keystream = keystream::recover(&cts, &[], &scorer);
+        assert_eq!(keystream.len(), pts.iter().map(Vec::len).max().unwrap());
+        assert!(accuracy(&cts, &keystream) > 0.97);
+        // The last bytes are only covered by a few ciphertexts.
+        assert!(keystream.confidence[0] > 0.99);
+        assert!(keystream.confidence.last().unwrap() < &0.5);
+
+        let cribs: [(usize, usize, &[u8]); 1] = [(0, 0, b"I'm")];
+        let keystream = keystream::recover(&cts, &cribs, &scorer);
+        assert_eq!(keystream.decrypt(&cts[0]), pts[0]);
+        assert_eq!(keystream.confidence[0], 1.0);
+
+        // The recovery only depends on the keystream being reused, not on how it was generated.
+        let mut iv = [0; 16];
+        rng.fill_bytes(&mut iv);
+        let cts = pts
+            .iter()
+            .map(|pt| {
+                let mut ct = vec![0; pt.len()];
+                ofb_encrypt(&key, &iv, pt, &mut ct);
+                ct
+            })
+            .collect::<Vec<Vec<u8>>>();
+        assert!(accuracy(&cts, &keystream::recover(&cts, &[], &scorer)) > 0.97);
+        let seed = rng.gen();
+        let cts = pts
+            .iter()
+            .map(|pt| {
+                let mut ct = vec![0; pt.len()];
+                encrypt(seed, pt, &mut ct);
+                ct
+            })
+            .collect::<Vec<Vec<u8>>>();
+        assert!(accuracy(&cts, &keystream::recover(&cts, &[], &scorer)) > 0.97);
+    }
+
+    #[test]
+    fn test_crib_drag() {
+        let mut rng = rand::thread_rng();
+        let mut key = [0; 16];
+        rng.fill_bytes(&mut key);
+        let nonce = 0;
+        let pts: Vec<Vec<u8>> = BufReader::new(File::open("src/set3/challenge19.txt").unwrap())
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| base64_to_bytes(&line))
+            .collect();
+        let cts: Vec<Vec<u8>> = pts
+            .iter()
+            .map(|pt| {
+                let mut ct = vec![0; pt.len()];
+                ctr_encrypt(&key, nonce, pt, &mut ct);
+                ct
+            })
+            .collect();
+
+        let candidates = drag(&cts, b" the ");
+        candidates.iter().take(5).for_each(|c| {
+            assert_eq!(&pts[c.ct_index][c.offset..c.offset + 5], b" the ");
+            assert!(c
+                .fragments
+                .iter()
+                .all(|(j, f)| f == &pts[*j][c.offset..c.offset + 5]));
+        });
+
+        // Committing cribs extends a partial keystream.
+        let mut keystream = Keystream::new();
+        candidates[0].commit(&mut keystream, &cts);
+        candidates[1].commit(&mut keystream, &cts);
+        let offset = candidates[0].offset;
+        let j = candidates[0].fragments[0].0;
+        assert_eq!(
+            keystream.decrypt(&cts[j])[offset..offset + 5],
+            pts[j][offset..offset + 5]
+        );
+        assert_eq!(keystream.confidence[offset], 1.0);
+    }
+
+    #[test]
+    fn test_mt19937_64_and_init_by_array() {
+        // Reference outputs from mt19937ar.c, mt19937-64.c and the C++ standard library.
+        let mut mt = MersenneTwister::new_mt19937();
+        mt.init_by_array(&[0x123, 0x234, 0x345, 0x456]);
+        assert_eq!(
+            (0..5).map(|_| mt.next().unwrap()).collect::<Vec<u32>>(),
+            [1067595299, 955945823, 477289528, 4107218783, 4228976476]
+        );
+        mt.seed(1812433253, 5489);
+        (0..9999).for_each(|_| {
+            mt.next();
+        });
+        assert_eq!(mt.next().unwrap(), 4123659995);
+
+        let mut mt = MersenneTwister64::new_mt19937_64();
+        assert_eq!(mt.next(), None);
+        mt.init_by_array(&[0x12345, 0x23456, 0x34567, 0x45678]);
+        assert_eq!(
+            (0..5).map(|_| mt.next().unwrap()).collect::<Vec<u64>>(),
+            [
+                7266447313870364031,
+                4946485549665804864,
+                16945909448695747420,
+                16394063075524226720,
+                4873882236456199058
+            ]
+        );
+        mt.seed(6364136223846793005, 5489);
+        (0..9999).for_each(|_| {
+            mt.next();
+        });
+        assert_eq!(mt.next().unwrap(), 9981545732273789042);
+
+        let mut mt = MersenneTwister64::new_mt19937_64();
+        mt.seed(6364136223846793005, 42);
+        let y = (0..312).map(|_| mt.next().unwrap()).collect::<Vec<u64>>();
+        let mut mt_ = clone_mt19937_64(&y);
+        (0..312).for_each(|_| assert_eq!(mt_.next(), mt.next()));
+    }
+
+    #[test]
+    fn test_recover_state() {
+        // Solving for the full MT19937 state takes too long for a test, so use a smaller twister with the same structure.
+        let small = || {
+            MersenneTwister::new(
+                17,
+                7,
+                31,
+                0x9908_B0DF,
+                0x9D2C_5680,
+                0xEFC6_0000,
+                7,
+                15,
+                11,
+                0xFFFF_FFFF,
+                18,
+            )
+        };
+        let mut mt = small();
+        mt.seed(1812433253, rand::thread_rng().gen());
+        let y = (0..600).map(|_| mt.next().unwrap()).collect::<Vec<u32>>();
+        let known_bits = |indices: &mut dyn Iterator<Item = usize>, bits: &[u32]| {
+            let y = &y;
+            indices
+                .flat_map(|i| bits.iter().map(move |&bit| (i, bit, y[i] >> bit & 1 == 1)))
+                .collect::<Vec<(usize, u32, bool)>>()
+        };
+
+        // Only the top 8 bits of every output, like a random byte.
+        let top_bits = (24..32).collect::<Vec<u32>>();
+        let mut mt_ = recover_state(&small(), &known_bits(&mut (0..100), &top_bits)).unwrap();
+        (0..600).for_each(|i| assert_eq!(mt_.next().unwrap(), y[i]));
+
+        // Only the low 4 bits of every third output, like rand() % 16 with skipped values.
+        let low_bits = (0..4).collect::<Vec<u32>>();
+        let mut mt_ =
+            recover_state(&small(), &known_bits(&mut (0..600).step_by(3), &low_bits)).unwrap();
+        (0..600).for_each(|i| assert_eq!(mt_.next().unwrap(), y[i]));
+
+        assert!(matches!(
+            recover_state(&small(), &known_bits(&mut (0..30), &top_bits)),
+            Err(RecoveryError::Underdetermined(_))
+        ));
+        let mut contradiction = known_bits(&mut (0..100), &top_bits);
+        contradiction.push((0, 31, y[0] >> 31 & 1 == 0));
+        assert_eq!(
+            recover_state(&small(), &contradiction).err(),
+            Some(RecoveryError::Inconsistent)
+        );
+    }
+
+    #[test]
+    fn test_previous_and_recover_seed() {
+        // Step back to the outputs that were generated before the ones we cloned from.
+        let n = 624;
+        let seed = rand::thread_rng().gen();
+        let mut mt = MersenneTwister::new_mt19937();
+        mt.seed(1812433253, seed);
+        let y = (0..2 * n).map(|_| mt.next().unwrap()).collect::<Vec<u32>>();
+        let mut mt_ = clone_mt19937(&y[n..]);
+        (1..2 * n)
+            .rev()
+            .for_each(|i| assert_eq!(mt_.previous().unwrap(), y[i]));
+        // The first output of an untwisted block is lost along with the lower bits of its word.
+        assert_eq!(mt_.previous(), None);
+        assert_eq!(mt_.next().unwrap(), y[1]);
+
+        // Freshly seeded generators have nothing to step back over.
+        let mut mt = MersenneTwister::new_mt19937();
+        mt.seed(1812433253, seed);
+        assert_eq!(mt.previous(), None);
+        assert_eq!(mt.next().unwrap(), y[0]);
+        assert_eq!(mt.previous().unwrap(), y[0]);
+        assert_eq!(mt.previous(), None);
+
+        let mut mt_ = clone_mt19937(&y[n..]);
+        mt_.untwist();
+        mt_.untwist();
+        assert_eq!(mt_.recover_seed(1812433253), Some(seed));
+        mt_.untwist();
+        assert_eq!(mt_.recover_seed(1812433253), None);
+
+        let mut mt = MersenneTwister::new_mt19937();
+        mt.seed(1812433253, 1_644_182_174);
+        let output = mt.next().unwrap();
+        assert_eq!(
+            brute_force_seeds(
+                &MersenneTwister::new_mt19937(),
+                1812433253,
+                1_644_162_174..=1_644_202_174,
+                &[(0, output)]
+            ),
+            [1_644_182_174]
+        );
+
+        let mut mt_ = clone_mt19937(&y[n..]);
+        mt_.untwist();
+        (1..n)
+            .rev()
+            .for_each(|i| assert_eq!(mt_.previous().unwrap(), y[i]));
+        assert_eq!(mt_.previous(), None);
+    }
+
+    #[test]
+    fn test_rng_core() {
+        // As a rand_core RNG, seeded like the reference implementations.
+        let mut rng = MersenneTwister::seed_from_u64(5489);
+        assert_eq!(rng.next_u32(), 3499211612);
+        assert_eq!(
+            MersenneTwister::from_seed(5489u32.to_le_bytes()).next_u32(),
+            3499211612
+        );
+        assert_ne!(
+            MersenneTwister::seed_from_u64(1 << 32).next_u32(),
+            MersenneTwister::seed_from_u64(0).next_u32()
+        );
+        let mut bytes = [0; 6];
+        rng.fill_bytes(&mut bytes);
+        assert_eq!(bytes[..4], 581869302u32.to_le_bytes());
+        assert_eq!(bytes[4..], 3890346734u32.to_le_bytes()[..2]);
+        let mut rng = MersenneTwister64::seed_from_u64(5489);
+        assert_eq!(rng.next_u64(), 14514284786278117030);
+        let mut rng = MersenneTwister::seed_from_u64(5489);
+        let n = rng.gen_range(0..1000);
+        assert_eq!(MersenneTwister::seed_from_u64(5489).gen_range(0..1000), n);
+    }
 }
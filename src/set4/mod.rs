@@ -3,8 +3,9 @@ mod tests {
     use crate::shared::aes::{
         cbc_decrypt, cbc_encrypt, ctr_decrypt, ctr_edit, ctr_encrypt, ecb_decrypt,
     };
-    use crate::shared::conversion::base64_to_bytes;
+    use crate::shared::conversion::Base64;
     use crate::shared::hmac::hmac;
+    use crate::shared::key_value::{Codec, Escaping, KeyValues};
     use crate::shared::md4::{md4_mac, MD4};
     use crate::shared::padding::{pad_pkcs7, unpad_pkcs7};
     use crate::shared::sha1::{sha1_mac, SHA1};
@@ -15,12 +16,9 @@ mod tests {
     #[test]
     fn test_challenge_25() {
         let key = b"YELLOW SUBMARINE";
-        let ct = base64_to_bytes(
-            &read_to_string("src/set1/challenge7.txt")
-                .unwrap()
-                .replace("\n", ""),
-        )
-        .unwrap();
+        let ct = Base64::new_mime()
+            .decode(&read_to_string("src/set1/challenge7.txt").unwrap())
+            .unwrap();
         let mut pt = vec![0; ct.len()];
         ecb_decrypt(key, &ct, &mut pt);
 
@@ -39,30 +37,35 @@ mod tests {
 
     #[test]
     fn test_challenge_26() {
+        let codec = Codec::new(';', '=', Escaping::Percent, true);
         let prefix = b"comment1=cooking%20MCs;userdata=";
-        let suffix = b";comment2=%20like%20a%20pound%20of%20bacon";
         let mut rng = rand::thread_rng();
         let mut key = [0; 16];
         rng.fill_bytes(&mut key);
         let nonce = 0;
-        let encrypt = |pt: &[u8]| {
-            if pt.contains(&b';') || pt.contains(&b'=') {
-                return None;
-            }
-
-            let pt = [prefix, pt, suffix].concat();
+        let encrypt = |userdata: &str| {
+            let map: KeyValues = [
+                ("comment1", "cooking MCs"),
+                ("userdata", userdata),
+                ("comment2", " like a pound of bacon"),
+            ]
+            .into_iter()
+            .collect();
+            // The strict encoder refuses metacharacters instead of escaping them.
+            let pt = codec.encode(&map).ok()?;
             let mut ct = vec![0; pt.len()];
-            ctr_encrypt(&key, nonce, &pt, &mut ct);
+            ctr_encrypt(&key, nonce, pt.as_bytes(), &mut ct);
             Some(ct)
         };
         let decrypt = |ct: &[u8]| {
             let mut pt = vec![0; ct.len()];
             ctr_decrypt(&key, nonce, &ct, &mut pt);
-            // We need from_utf8_lossy here because the second block will be scrambled.
-            String::from_utf8_lossy(&pt).contains(";admin=true;")
+            let map = codec.decode(&String::from_utf8(pt).unwrap()).unwrap();
+            map.get("admin") == Some("true")
         };
 
-        let mut ct = encrypt(b"?admin?true").unwrap();
+        assert!(encrypt(";admin=true").is_none());
+        let mut ct = encrypt("?admin?true").unwrap();
         let actual_pt = b"?admin?true";
         let target_pt = b";admin=true";
         (0..actual_pt.len()).for_each(|i| ct[prefix.len() + i] ^= actual_pt[i] ^ target_pt[i]);
@@ -2,7 +2,7 @@ use crate::shared::xor::xor;
 use aes::cipher::generic_array::GenericArray;
 use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
 use aes::Aes128;
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 pub fn ecb_encrypt(key: &[u8; 16], pt: &[u8], ct: &mut [u8]) {
     assert_eq!(pt.len() % 16, 0);
@@ -32,10 +32,25 @@ pub fn ecb_decrypt(key: &[u8; 16], ct: &[u8], pt: &mut [u8]) {
     }
 }
 
+// Returns, for every whole block, the index of its first occurrence if it occurs more than once.
+#[must_use]
+pub fn repeated_blocks(ct: &[u8], block_size: usize) -> Vec<Option<usize>> {
+    let mut first_occurrences: HashMap<&[u8], usize> = HashMap::new();
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    ct.chunks_exact(block_size)
+        .enumerate()
+        .for_each(|(i, block)| {
+            first_occurrences.entry(block).or_insert(i);
+            *counts.entry(block).or_insert(0) += 1;
+        });
+    ct.chunks_exact(block_size)
+        .map(|block| Some(first_occurrences[block]).filter(|_| counts[block] > 1))
+        .collect()
+}
+
 #[must_use]
 pub fn is_ecb(ct: &[u8]) -> bool {
-    let blocks_set: HashSet<&[u8]> = ct.chunks_exact(16).collect();
-    blocks_set.len() < ct.len() / 16
+    repeated_blocks(ct, 16).iter().any(Option::is_some)
 }
 
 pub fn cbc_encrypt(key: &[u8; 16], iv: &[u8; 16], pt: &[u8], ct: &mut [u8]) {
@@ -94,6 +109,27 @@ pub fn ctr_decrypt(key: &[u8; 16], nonce: u64, ct: &[u8], pt: &mut [u8]) {
     ctr_encrypt(key, nonce, ct, pt);
 }
 
+fn ofb_keystream(aes128: Aes128, iv: &[u8; 16]) -> impl Iterator<Item = u8> {
+    let mut keystream_block = *GenericArray::from_slice(iv);
+    (0u64..).flat_map(move |_| {
+        aes128.encrypt_block(&mut keystream_block);
+        keystream_block
+    })
+}
+
+pub fn ofb_encrypt(key: &[u8; 16], iv: &[u8; 16], pt: &[u8], ct: &mut [u8]) {
+    assert_eq!(ct.len(), pt.len());
+    let aes128 = Aes128::new(GenericArray::from_slice(key));
+    ofb_keystream(aes128, iv)
+        .take(pt.len())
+        .enumerate()
+        .for_each(|(i, k)| ct[i] = pt[i] ^ k);
+}
+
+pub fn ofb_decrypt(key: &[u8; 16], iv: &[u8; 16], ct: &[u8], pt: &mut [u8]) {
+    ofb_encrypt(key, iv, ct, pt);
+}
+
 pub fn ctr_edit(key: &[u8; 16], nonce: u64, ct: &mut [u8], offset: usize, pt: &[u8]) {
     assert_eq!(pt.len(), ct.len() - offset);
     let aes128 = Aes128::new(GenericArray::from_slice(key));
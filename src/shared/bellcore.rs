@@ -0,0 +1,35 @@
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::One;
+
+// If only one CRT half of the signature is faulty, the signature is still correct modulo the other prime.
+// With the correct signature, gcd(s - s', n) reveals that prime. Without it, gcd(s'^e - m, n) does (Lenstra).
+// m is the signed representative, e.g. rsa::encode_signature(n, msg).
+#[must_use]
+pub fn factor(
+    public_key: &(BigUint, BigUint),
+    m: &BigUint,
+    faulty: &BigUint,
+    correct: Option<&BigUint>,
+) -> Option<(BigUint, BigUint)> {
+    let (n, e) = public_key;
+    let difference = match correct {
+        Some(s) if s >= faulty => s - faulty,
+        Some(s) => faulty - s,
+        None => {
+            let m_ = faulty.modpow(e, n);
+            if &m_ >= m {
+                m_ - m
+            } else {
+                m - m_
+            }
+        }
+    };
+    let p = difference.gcd(n);
+    if p.is_one() || &p == n {
+        return None;
+    }
+
+    let q = n / &p;
+    Some((p, q))
+}
@@ -0,0 +1,165 @@
+use crate::shared::lattice::lll;
+use crate::shared::polynomial::{integer_roots, interpolate, resultant};
+use crate::shared::rsa::PrivateKey;
+use crate::shared::wiener::private_key_from_guess;
+use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_traits::{One, Zero};
+use std::collections::HashMap;
+
+// The exponents (a, b, c) of x^a * u^b * y^c, where u = 1 + x * y. Because of that relation a and c are never both nonzero.
+type Monomial = (u32, u32, u32);
+
+fn binomial(n: u32, k: u32) -> BigInt {
+    (0..k).fold(BigInt::one(), |c, i| c * (n - i) / (i + 1))
+}
+
+// x^i * y^j * f^k * e^(m - k) with f = u + A * x, where every x * y is replaced by u - 1.
+fn shift((i, j, k): (u32, u32, u32), m: u32, a: &BigInt, e: &BigInt) -> HashMap<Monomial, BigInt> {
+    let mut poly = HashMap::new();
+    let e_power = e.pow(m - k);
+    for l in 0..=k {
+        // The term C(k, l) * A^(k - l) * x^(k - l) * u^l, times x^i * y^j.
+        let coefficient = binomial(k, l) * a.pow(k - l) * &e_power;
+        let x_degree = i + k - l;
+        let r = x_degree.min(j);
+        for s in 0..=r {
+            // (x * y)^r = (u - 1)^r.
+            let mut term = &coefficient * binomial(r, s);
+            if (r - s) % 2 == 1 {
+                term = -term;
+            }
+            *poly
+                .entry((x_degree - r, l + s, j - r))
+                .or_insert_with(BigInt::zero) += term;
+        }
+    }
+    poly
+}
+
+// Substitutes u = 1 + x * y back, giving the coefficients of x^a * y^c at [a][c].
+fn to_bivariate(poly: &HashMap<Monomial, BigInt>) -> Vec<Vec<BigInt>> {
+    let x_degree = poly.keys().map(|&(a, b, _)| a + b).max().unwrap_or(0);
+    let y_degree = poly.keys().map(|&(_, b, c)| b + c).max().unwrap_or(0);
+    let mut bivariate = vec![
+        vec![BigInt::zero(); usize::try_from(y_degree).unwrap() + 1];
+        usize::try_from(x_degree).unwrap() + 1
+    ];
+    poly.iter().for_each(|(&(a, b, c), coefficient)| {
+        (0..=b).for_each(|s| {
+            let (i, j) = (
+                usize::try_from(a + s).unwrap(),
+                usize::try_from(c + s).unwrap(),
+            );
+            bivariate[i][j] += coefficient * binomial(b, s);
+        });
+    });
+    bivariate
+}
+
+// The resultant of g and h with respect to y, as a polynomial in x.
+fn resultant_in_y(g: &[Vec<BigInt>], h: &[Vec<BigInt>]) -> Vec<BigInt> {
+    let at = |poly: &[Vec<BigInt>], x: &BigInt| {
+        let y_degree = poly[0].len();
+        (0..y_degree)
+            .map(|c| {
+                poly.iter()
+                    .rev()
+                    .fold(BigInt::zero(), |result, row| result * x + &row[c])
+            })
+            .collect::<Vec<BigInt>>()
+    };
+    let degree = (g.len() - 1) * (h[0].len() - 1) + (h.len() - 1) * (g[0].len() - 1);
+    let values = (0..=degree)
+        .map(|x| {
+            let x = BigInt::from(x);
+            resultant(&at(g, &x), &at(h, &x))
+        })
+        .collect::<Vec<BigInt>>();
+    interpolate(&values)
+}
+
+// Boneh and Durfee's attack for d < 2^d_bits, which works up to d < n^0.292 given a large enough lattice.
+// With A = (n + 1) / 2 and s = p + q, e * d = 1 + k * phi(n) means 1 + x * (A + y) = 0 mod e has the small root
+// x = 2k, y = -s / 2. This uses Herrmann and May's unravelled linearisation u = 1 + x * y, which makes the lattice
+// triangular: x-shifts x^i * f^k * e^(m - k) and y-shifts y^j * f^k * e^(m - k) for k >= floor(m / t) * j.
+// Short reduced rows are polynomials that have the root over the integers, so the resultant of two of them gives x.
+#[must_use]
+pub fn attack(public_key: &(BigUint, BigUint), d_bits: u64, m: u32, t: u32) -> Option<PrivateKey> {
+    let (n, e) = public_key;
+    let a = &((n + 1u8) / 2u8).to_bigint().unwrap();
+    let e_ = &e.to_bigint().unwrap();
+    let x_bound = BigInt::one() << (d_bits + 1);
+    // This assumes balanced primes, like the ones from rsa::PrivateKey::generate, so (p + q) / 2 < 2^ceil(bits / 2).
+    let y_bound = BigInt::one() << n.bits().div_ceil(2);
+    let u_bound = &x_bound * &y_bound + 1u8;
+
+    // Every shift introduces exactly one new monomial, x^i * u^k or u^k * y^j.
+    let mut shifts = Vec::new();
+    for k in 0..=m {
+        for i in 0..=m - k {
+            shifts.push(((i, 0, k), (i, k, 0)));
+        }
+    }
+    for j in 1..=t {
+        for k in (m / t) * j..=m {
+            shifts.push(((0, j, k), (0, k, j)));
+        }
+    }
+    let monomials = shifts
+        .iter()
+        .map(|&(_, monomial)| monomial)
+        .collect::<Vec<Monomial>>();
+    let scales = monomials
+        .iter()
+        .map(|&(a, b, c)| x_bound.pow(a) * u_bound.pow(b) * y_bound.pow(c))
+        .collect::<Vec<BigInt>>();
+    let basis = shifts
+        .iter()
+        .map(|&(exponents, _)| {
+            let poly = shift(exponents, m, a, e_);
+            monomials
+                .iter()
+                .zip(&scales)
+                .map(|(monomial, scale)| poly.get(monomial).map_or(BigInt::zero(), |c| c * scale))
+                .collect()
+        })
+        .collect::<Vec<Vec<BigInt>>>();
+
+    let polys = lll(&basis)?
+        .iter()
+        .map(|row| {
+            let poly = monomials
+                .iter()
+                .zip(&scales)
+                .zip(row)
+                .filter(|(_, c)| !c.is_zero())
+                .map(|((&monomial, scale), c)| (monomial, c / scale))
+                .collect::<HashMap<Monomial, BigInt>>();
+            to_bivariate(&poly)
+        })
+        .collect::<Vec<Vec<Vec<BigInt>>>>();
+
+    // Not every reduced row vanishes at the root, and rows can share a factor, so try pairs until one works.
+    for (g_index, g) in polys.iter().enumerate() {
+        for h in polys.iter().skip(g_index + 1) {
+            let r = resultant_in_y(g, h);
+            if r.iter().all(Zero::is_zero) {
+                continue;
+            }
+
+            let private_key = integer_roots(&r, &BigInt::one(), &x_bound)
+                .into_iter()
+                .filter_map(|x| x.to_biguint())
+                .find_map(|x| {
+                    let k = x / 2u8;
+                    // e * d = 1 + k * (n + 1 - s), so k * n / e is d plus a positive fraction below 1.
+                    let d = &k * n / e;
+                    private_key_from_guess(public_key, &k, &d)
+                });
+            if private_key.is_some() {
+                return private_key;
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,339 @@
+use crate::shared::xor::{
+    estimate_key_sizes_ioc, shortest_period, transpose, NGram, Scorer, LETTER_FREQUENCIES,
+};
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+// Maps every letter to another letter (as an index into the alphabet), preserving case. Other bytes are left alone.
+fn map_letters<F: FnMut(u8) -> u8>(text: &[u8], mut f: F) -> Vec<u8> {
+    text.iter()
+        .map(|&b| {
+            let base = if b.is_ascii_uppercase() {
+                b'A'
+            } else if b.is_ascii_lowercase() {
+                b'a'
+            } else {
+                return b;
+            };
+            base + f(b - base) % 26
+        })
+        .collect()
+}
+
+#[must_use]
+pub fn letters(text: &[u8]) -> Vec<u8> {
+    text.iter()
+        .filter(|b| b.is_ascii_alphabetic())
+        .map(u8::to_ascii_lowercase)
+        .collect()
+}
+
+fn chi_squared(text: &[u8]) -> f64 {
+    let letters = letters(text);
+    let mut observed = [0u32; 26];
+    letters
+        .iter()
+        .for_each(|&b| observed[usize::from(b - b'a')] += 1);
+    // Without letters every candidate decrypts to the same text, so they're all equally likely.
+    if letters.is_empty() {
+        return 0.0;
+    }
+
+    let total = f64::from(u32::try_from(letters.len()).unwrap());
+    observed
+        .iter()
+        .zip(LETTER_FREQUENCIES)
+        .map(|(&o, f)| {
+            let e = f / 100.0 * total;
+            (f64::from(o) - e).powi(2) / e
+        })
+        .sum()
+}
+
+#[must_use]
+pub fn caesar_encrypt(pt: &[u8], shift: u8) -> Vec<u8> {
+    map_letters(pt, |x| x + shift % 26)
+}
+
+#[must_use]
+pub fn caesar_decrypt(ct: &[u8], shift: u8) -> Vec<u8> {
+    caesar_encrypt(ct, 26 - shift % 26)
+}
+
+#[must_use]
+pub fn break_caesar(ct: &[u8]) -> (u8, Vec<u8>) {
+    (0..26)
+        .map(|shift| (shift, caesar_decrypt(ct, shift)))
+        .min_by(|(_, a), (_, b)| chi_squared(a).total_cmp(&chi_squared(b)))
+        .unwrap()
+}
+
+// The key only advances on letters, so punctuation and spaces don't consume key letters.
+#[must_use]
+pub fn vigenere_encrypt(pt: &[u8], key: &[u8]) -> Vec<u8> {
+    let shifts = letters(key)
+        .into_iter()
+        .map(|k| k - b'a')
+        .collect::<Vec<u8>>();
+    assert!(!shifts.is_empty());
+    let mut i = 0;
+    map_letters(pt, |x| {
+        let shift = shifts[i % shifts.len()];
+        i += 1;
+        x + shift
+    })
+}
+
+#[must_use]
+pub fn vigenere_decrypt(ct: &[u8], key: &[u8]) -> Vec<u8> {
+    let inverse_key = letters(key)
+        .into_iter()
+        .map(|k| b'a' + (26 - (k - b'a')) % 26)
+        .collect::<Vec<u8>>();
+    vigenere_encrypt(ct, &inverse_key)
+}
+
+#[must_use]
+pub fn break_vigenere(ct: &[u8], max_key_size: usize) -> Option<(Vec<u8>, Vec<u8>)> {
+    let letters = letters(ct);
+    let key_sizes = estimate_key_sizes_ioc(&letters, max_key_size);
+    let (_, best) = key_sizes.first()?;
+    // Multiples of the key size score about as well as the key size itself, so take the smallest one that comes close.
+    let key_size = key_sizes
+        .iter()
+        .filter(|(_, ioc)| *ioc >= 0.9 * best)
+        .map(|(key_size, _)| *key_size)
+        .min()?;
+    let mut key = (0..key_size)
+        .map(|offset| b'a' + break_caesar(&transpose(&letters, key_size, offset)).0)
+        .collect::<Vec<u8>>();
+    key.truncate(shortest_period(&key));
+    let pt = vigenere_decrypt(ct, &key);
+    Some((key, pt))
+}
+
+fn affine_inverse(a: u8) -> Option<u8> {
+    (1..26).find(|&x| (u16::from(a) * u16::from(x)) % 26 == 1)
+}
+
+#[must_use]
+pub fn affine_encrypt(pt: &[u8], a: u8, b: u8) -> Vec<u8> {
+    assert!(affine_inverse(a % 26).is_some());
+    map_letters(pt, |x| {
+        u8::try_from((u16::from(a) * u16::from(x) + u16::from(b)) % 26).unwrap()
+    })
+}
+
+#[must_use]
+pub fn affine_decrypt(ct: &[u8], a: u8, b: u8) -> Vec<u8> {
+    let a_inv = affine_inverse(a % 26).unwrap();
+    map_letters(ct, |y| {
+        u8::try_from(u16::from(a_inv) * (u16::from(y) + 26 - u16::from(b % 26)) % 26).unwrap()
+    })
+}
+
+#[must_use]
+pub fn break_affine(ct: &[u8]) -> ((u8, u8), Vec<u8>) {
+    (1..26)
+        .filter(|&a| affine_inverse(a).is_some())
+        .flat_map(|a| (0..26).map(move |b| (a, b)))
+        .map(|(a, b)| ((a, b), affine_decrypt(ct, a, b)))
+        .min_by(|(_, a), (_, b)| chi_squared(a).total_cmp(&chi_squared(b)))
+        .unwrap()
+}
+
+// The key maps the i-th letter of the alphabet to key[i].
+#[must_use]
+pub fn substitution_encrypt(pt: &[u8], key: &[u8; 26]) -> Vec<u8> {
+    map_letters(pt, |x| key[usize::from(x)].to_ascii_lowercase() - b'a')
+}
+
+#[must_use]
+pub fn substitution_decrypt(ct: &[u8], key: &[u8; 26]) -> Vec<u8> {
+    let mut inverse_key = [0; 26];
+    key.iter().enumerate().for_each(|(i, &k)| {
+        inverse_key[usize::from(k.to_ascii_lowercase() - b'a')] = b'a' + u8::try_from(i).unwrap()
+    });
+    substitution_encrypt(ct, &inverse_key)
+}
+
+// Hill climbs over letter swaps from random keys, keeping the key that gives the best scoring plaintext.
+// Only the letters are scored, so the n-grams should be something like NGram::new_english_letter_quadgrams.
+#[must_use]
+pub fn break_substitution(ct: &[u8], ngrams: &NGram, restarts: usize) -> ([u8; 26], Vec<u8>) {
+    break_substitution_with_rng(ct, ngrams, restarts, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn break_substitution_with_rng<R: RngCore + ?Sized>(
+    ct: &[u8],
+    ngrams: &NGram,
+    restarts: usize,
+    rng: &mut R,
+) -> ([u8; 26], Vec<u8>) {
+    let n = ngrams.n();
+    assert!(n <= 4);
+    // Look up every letter n-gram once, so scoring a key doesn't need to hash anything.
+    let mut ngram = vec![b'a'; n];
+    let table = (0..26usize.pow(u32::try_from(n).unwrap()))
+        .map(|index| {
+            let mut x = index;
+            (0..n).rev().for_each(|k| {
+                ngram[k] = b'a' + u8::try_from(x % 26).unwrap();
+                x /= 26;
+            });
+            ngrams.log_probability(&ngram)
+        })
+        .collect::<Vec<f64>>();
+    let letters = letters(ct);
+    let score = |key: &[u8; 26]| {
+        let mut inverse_key = [0; 26];
+        key.iter()
+            .enumerate()
+            .for_each(|(i, &k)| inverse_key[usize::from(k - b'a')] = i);
+        letters
+            .windows(n)
+            .map(|ngram| {
+                table[ngram.iter().fold(0, |index, &c| {
+                    index * 26 + inverse_key[usize::from(c - b'a')]
+                })]
+            })
+            .sum::<f64>()
+    };
+    // Start by matching the ciphertext's letter frequencies to the English ones.
+    let mut observed = [0u32; 26];
+    letters
+        .iter()
+        .for_each(|&b| observed[usize::from(b - b'a')] += 1);
+    let mut by_observed = (0..26).collect::<Vec<usize>>();
+    by_observed.sort_by_key(|&i| std::cmp::Reverse(observed[i]));
+    let mut by_expected = (0..26).collect::<Vec<usize>>();
+    by_expected.sort_by(|&i, &j| LETTER_FREQUENCIES[j].total_cmp(&LETTER_FREQUENCIES[i]));
+    let mut frequency_key = [0; 26];
+    by_expected
+        .iter()
+        .zip(&by_observed)
+        .for_each(|(&p, &c)| frequency_key[p] = b'a' + u8::try_from(c).unwrap());
+
+    let mut best_key = frequency_key;
+    let mut best_score = score(&best_key);
+    (0..=restarts).for_each(|restart| {
+        let mut key = frequency_key;
+        if restart > 0 {
+            key.shuffle(rng);
+        }
+        let mut key_score = score(&key);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for i in 0..26 {
+                for j in i + 1..26 {
+                    key.swap(i, j);
+                    let s = score(&key);
+                    if s > key_score {
+                        key_score = s;
+                        improved = true;
+                    } else {
+                        key.swap(i, j);
+                    }
+                }
+            }
+        }
+
+        if key_score > best_score {
+            best_key = key;
+            best_score = key_score;
+        }
+    });
+    (best_key, substitution_decrypt(ct, &best_key))
+}
+
+// Returns the order in which the columns are read, sorting the keyword's letters and breaking ties by position.
+#[must_use]
+pub fn keyword_order(keyword: &[u8]) -> Vec<usize> {
+    let mut order = (0..keyword.len()).collect::<Vec<usize>>();
+    order.sort_by_key(|&i| (keyword[i].to_ascii_lowercase(), i));
+    order
+}
+
+#[must_use]
+pub fn columnar_encrypt(pt: &[u8], order: &[usize]) -> Vec<u8> {
+    order
+        .iter()
+        .flat_map(|&column| transpose(pt, order.len(), column))
+        .collect()
+}
+
+#[must_use]
+pub fn columnar_decrypt(ct: &[u8], order: &[usize]) -> Vec<u8> {
+    let width = order.len();
+    let mut pt = vec![0; ct.len()];
+    let mut i = 0;
+    order.iter().for_each(|&column| {
+        let mut j = column;
+        while j < ct.len() {
+            pt[j] = ct[i];
+            i += 1;
+            j += width;
+        }
+    });
+    pt
+}
+
+// Hill climbs over column swaps for every width, keeping the order that gives the best scoring plaintext.
+#[must_use]
+pub fn break_columnar<S: Scorer + ?Sized>(
+    ct: &[u8],
+    max_width: usize,
+    scorer: &S,
+    restarts: usize,
+) -> (Vec<usize>, Vec<u8>) {
+    break_columnar_with_rng(ct, max_width, scorer, restarts, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn break_columnar_with_rng<S: Scorer + ?Sized, R: RngCore + ?Sized>(
+    ct: &[u8],
+    max_width: usize,
+    scorer: &S,
+    restarts: usize,
+    rng: &mut R,
+) -> (Vec<usize>, Vec<u8>) {
+    let score = |order: &[usize]| {
+        scorer
+            .score(&columnar_decrypt(ct, order))
+            .unwrap_or(f64::NEG_INFINITY)
+    };
+    let mut best_order = vec![0];
+    let mut best_score = score(&best_order);
+    (2..=max_width.min(ct.len())).for_each(|width| {
+        (0..restarts).for_each(|_| {
+            let mut order = (0..width).collect::<Vec<usize>>();
+            order.shuffle(rng);
+            let mut order_score = score(&order);
+            let mut improved = true;
+            while improved {
+                improved = false;
+                for i in 0..width {
+                    for j in i + 1..width {
+                        order.swap(i, j);
+                        let s = score(&order);
+                        if s > order_score {
+                            order_score = s;
+                            improved = true;
+                        } else {
+                            order.swap(i, j);
+                        }
+                    }
+                }
+            }
+
+            if order_score > best_score {
+                best_order = order;
+                best_score = order_score;
+            }
+        });
+    });
+    let pt = columnar_decrypt(ct, &best_order);
+    (best_order, pt)
+}
@@ -0,0 +1,35 @@
+use crate::shared::{egcd, mod_inv};
+use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_traits::{One, Signed};
+
+// c^s mod n, where a negative s means raising the inverse of c.
+fn signed_modpow(c: &BigUint, s: &BigInt, n: &BigUint) -> Option<BigUint> {
+    let exponent = s.abs().to_biguint().unwrap();
+    if s.is_negative() {
+        Some(mod_inv(c, n)?.modpow(&exponent, n))
+    } else {
+        Some(c.modpow(&exponent, n))
+    }
+}
+
+// The same message encrypted under one modulus with two coprime exponents e1 and e2.
+// With s1 * e1 + s2 * e2 = 1 from egcd, c1^s1 * c2^s2 = m^(s1 * e1 + s2 * e2) = m mod n.
+#[must_use]
+pub fn attack(
+    public_key1: &(BigUint, BigUint),
+    c1: &BigUint,
+    public_key2: &(BigUint, BigUint),
+    c2: &BigUint,
+) -> Option<BigUint> {
+    let ((n, e1), (n_, e2)) = (public_key1, public_key2);
+    if n != n_ {
+        return None;
+    }
+
+    let (g, s1, s2) = egcd(&e1.to_bigint().unwrap(), &e2.to_bigint().unwrap());
+    if !g.is_one() {
+        return None;
+    }
+
+    Some(signed_modpow(c1, &s1, n)? * signed_modpow(c2, &s2, n)? % n)
+}
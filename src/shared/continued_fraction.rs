@@ -0,0 +1,34 @@
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+// The partial quotients of numerator / denominator.
+#[must_use]
+pub fn expansion(numerator: &BigUint, denominator: &BigUint) -> Vec<BigUint> {
+    let mut quotients = Vec::new();
+    let (mut a, mut b) = (numerator.clone(), denominator.clone());
+    while !b.is_zero() {
+        let (q, r) = a.div_rem(&b);
+        quotients.push(q);
+        a = b;
+        b = r;
+    }
+    quotients
+}
+
+// The convergents as (numerator, denominator) pairs, from the first partial quotient on.
+#[must_use]
+pub fn convergents(quotients: &[BigUint]) -> Vec<(BigUint, BigUint)> {
+    let (mut h_prev, mut h) = (BigUint::zero(), BigUint::one());
+    let (mut k_prev, mut k) = (BigUint::one(), BigUint::zero());
+    quotients
+        .iter()
+        .map(|a| {
+            let h_next = a * &h + &h_prev;
+            let k_next = a * &k + &k_prev;
+            h_prev = std::mem::replace(&mut h, h_next);
+            k_prev = std::mem::replace(&mut k, k_next);
+            (h.clone(), k.clone())
+        })
+        .collect()
+}
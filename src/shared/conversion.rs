@@ -1,3 +1,7 @@
+use crate::shared::aes::repeated_blocks;
+use crate::shared::sha256::SHA256;
+use num_bigint::BigUint;
+
 const HEX_ENCODE: [char; 16] = [
     '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'a', 'b', 'c', 'd', 'e', 'f',
 ];
@@ -28,24 +32,22 @@ const BASE64_ENCODE: [char; 64] = [
     '5', '6', '7', '8', '9', '+', '/',
 ];
 
-const BASE64_DECODE: [u8; 256] = [
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 62, 0xFF, 0xFF, 0xFF, 63, 52,
-    53, 54, 55, 56, 57, 58, 59, 60, 61, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 1, 2, 3, 4, 5,
-    6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44,
-    45, 46, 47, 48, 49, 50, 51, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
-    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+const BASE64_URL_SAFE_ENCODE: [char; 64] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l',
+    'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4',
+    '5', '6', '7', '8', '9', '-', '_',
 ];
 
+const BASE32_ENCODE: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+const BASE32_HEX_ENCODE: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+const BASE58_ENCODE: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+const Z85_ENCODE: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
 #[must_use]
 pub fn bytes_to_hex(bytes: &[u8]) -> String {
     let mut hex = String::with_capacity(2 * bytes.len());
@@ -61,6 +63,11 @@ pub fn bytes_to_hex(bytes: &[u8]) -> String {
     hex
 }
 
+#[must_use]
+pub fn bytes_to_upper_hex(bytes: &[u8]) -> String {
+    bytes_to_hex(bytes).to_ascii_uppercase()
+}
+
 #[must_use]
 pub fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
     let mut bytes = Vec::with_capacity(hex.len() / 2);
@@ -88,81 +95,656 @@ pub fn hex_to_bytes(hex: &str) -> Option<Vec<u8>> {
     Some(bytes)
 }
 
-#[must_use]
-pub fn bytes_to_base64(bytes: &[u8]) -> String {
-    let mut base64 = String::with_capacity(4 * ((bytes.len() + 2) / 3));
-    let mut iter = bytes.iter();
-    let mut i = 0;
-    while i < bytes.len() {
-        let mut triple = 0;
-        let mut j = 0;
-        while j < 3 {
-            i += 1;
-            match iter.next() {
-                Some(&b) => triple += u32::from(b) << ((2 - j) * 8),
-                // Break early to allow for padding.
-                None => break,
+// Offsets are byte offsets into the string being decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidCharacter(usize),
+    InvalidPadding(usize),
+    InvalidLength(usize),
+    InvalidChecksum,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alphabet {
+    Standard,
+    UrlSafe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    Required,
+    Optional,
+    Forbidden,
+}
+
+pub struct Base64 {
+    encode: [char; 64],
+    decode: [u8; 256],
+    padding: Padding,
+    line_length: Option<usize>,
+    skip_whitespace: bool,
+}
+
+impl Base64 {
+    #[must_use]
+    pub fn new(
+        alphabet: Alphabet,
+        padding: Padding,
+        line_length: Option<usize>,
+        skip_whitespace: bool,
+    ) -> Self {
+        let encode = match alphabet {
+            Alphabet::Standard => BASE64_ENCODE,
+            Alphabet::UrlSafe => BASE64_URL_SAFE_ENCODE,
+        };
+        let mut decode = [0xFF; 256];
+        encode.iter().enumerate().for_each(|(i, &c)| {
+            decode[usize::from(u8::try_from(c).unwrap())] = u8::try_from(i).unwrap()
+        });
+        Self {
+            encode,
+            decode,
+            padding,
+            line_length,
+            skip_whitespace,
+        }
+    }
+
+    #[must_use]
+    pub fn new_standard() -> Self {
+        Self::new(Alphabet::Standard, Padding::Required, None, false)
+    }
+
+    #[must_use]
+    pub fn new_url_safe() -> Self {
+        Self::new(Alphabet::UrlSafe, Padding::Forbidden, None, false)
+    }
+
+    #[must_use]
+    pub fn new_mime() -> Self {
+        Self::new(Alphabet::Standard, Padding::Required, Some(76), true)
+    }
+
+    #[must_use]
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut base64 = String::with_capacity(4 * bytes.len().div_ceil(3));
+        bytes.chunks(3).for_each(|chunk| {
+            let mut triple = 0;
+            chunk
+                .iter()
+                .enumerate()
+                .for_each(|(j, &b)| triple += u32::from(b) << ((2 - j) * 8));
+            (0..=chunk.len()).for_each(|k| {
+                base64.push(self.encode[usize::try_from((triple >> ((3 - k) * 6)) % 64).unwrap()]);
+            });
+            if self.padding != Padding::Forbidden {
+                (chunk.len() + 1..4).for_each(|_| base64.push('='));
             }
-            j += 1;
+        });
+
+        match self.line_length {
+            Some(line_length) if line_length > 0 && base64.len() > line_length => base64
+                .as_bytes()
+                .chunks(line_length)
+                .map(|line| String::from_utf8(line.to_vec()).unwrap())
+                .collect::<Vec<String>>()
+                .join("\r\n"),
+            _ => base64,
         }
+    }
 
+    pub fn decode(&self, base64: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::with_capacity(3 * (base64.len() / 4));
+        let mut quad = [0; 4];
         let mut k = 0;
-        while k < j + 1 {
-            base64.push(BASE64_ENCODE[usize::try_from((triple >> ((3 - k) * 6)) % 64).unwrap()]);
-            k += 1;
-        }
-        while k < 4 {
-            base64.push('=');
+        let mut padding = 0;
+        let mut padding_offset = 0;
+        for (i, b) in base64.bytes().enumerate() {
+            if self.skip_whitespace && b.is_ascii_whitespace() {
+                continue;
+            }
+
+            if b == b'=' {
+                // Padding is only allowed after at least two characters, and never fills more than the quad.
+                if self.padding == Padding::Forbidden || k < 2 || k + padding == 4 {
+                    return Err(DecodeError::InvalidPadding(i));
+                }
+                if padding == 0 {
+                    padding_offset = i;
+                }
+                padding += 1;
+                continue;
+            }
+
+            if padding > 0 {
+                // Nothing but padding may follow padding.
+                return Err(DecodeError::InvalidPadding(i));
+            }
+
+            let b = self.decode[usize::from(b)];
+            if b == 0xFF {
+                return Err(DecodeError::InvalidCharacter(i));
+            }
+
+            quad[k] = b;
             k += 1;
+            if k == 4 {
+                let triple = quad.iter().enumerate().fold(0, |triple, (k, &b)| {
+                    triple + (u32::from(b) << ((3 - k) * 6))
+                });
+                bytes.extend_from_slice(&triple.to_be_bytes()[1..4]);
+                k = 0;
+            }
         }
-        if j != 3 {
-            // Break early because the string is padded.
-            break;
+
+        match k {
+            0 => {}
+            1 => return Err(DecodeError::InvalidLength(base64.len())),
+            _ => {
+                if padding == 0 && self.padding == Padding::Required {
+                    return Err(DecodeError::InvalidPadding(base64.len()));
+                }
+                if padding != 0 && k + padding != 4 {
+                    return Err(DecodeError::InvalidPadding(padding_offset));
+                }
+
+                let triple = quad[0..k].iter().enumerate().fold(0, |triple, (k, &b)| {
+                    triple + (u32::from(b) << ((3 - k) * 6))
+                });
+                bytes.extend_from_slice(&triple.to_be_bytes()[1..k]);
+            }
         }
+
+        Ok(bytes)
     }
+}
 
-    base64
+#[must_use]
+pub fn bytes_to_base64(bytes: &[u8]) -> String {
+    Base64::new_standard().encode(bytes)
 }
 
 #[must_use]
 pub fn base64_to_bytes(base64: &str) -> Option<Vec<u8>> {
-    let mut bytes = Vec::with_capacity(3 * (base64.len() / 4));
-    let mut iter = base64.bytes();
-    let mut i = 0;
-    while i < base64.len() {
-        let mut triple = 0;
+    Base64::new_standard().decode(base64).ok()
+}
+
+pub struct Base32 {
+    encode: &'static [u8; 32],
+    decode: [u8; 256],
+    padding: Padding,
+}
+
+impl Base32 {
+    #[must_use]
+    pub fn new(extended_hex: bool, padding: Padding) -> Self {
+        let encode = if extended_hex {
+            BASE32_HEX_ENCODE
+        } else {
+            BASE32_ENCODE
+        };
+        let mut decode = [0xFF; 256];
+        encode.iter().enumerate().for_each(|(i, &c)| {
+            // Lowercase input is accepted as well.
+            decode[usize::from(c)] = u8::try_from(i).unwrap();
+            decode[usize::from(c.to_ascii_lowercase())] = u8::try_from(i).unwrap();
+        });
+        Self {
+            encode,
+            decode,
+            padding,
+        }
+    }
+
+    #[must_use]
+    pub fn new_standard() -> Self {
+        Self::new(false, Padding::Required)
+    }
+
+    #[must_use]
+    pub fn new_hex() -> Self {
+        Self::new(true, Padding::Required)
+    }
+
+    #[must_use]
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut base32 = String::with_capacity(8 * bytes.len().div_ceil(5));
+        bytes.chunks(5).for_each(|chunk| {
+            let mut quintuple = [0; 8];
+            quintuple[8 - chunk.len()..].copy_from_slice(chunk);
+            let quintuple = u64::from_be_bytes(quintuple) << (8 * (5 - chunk.len()));
+            let k = (8 * chunk.len()).div_ceil(5);
+            (0..k).for_each(|j| {
+                let c = self.encode[usize::try_from((quintuple >> ((7 - j) * 5)) % 32).unwrap()];
+                base32.push(char::from(c));
+            });
+            if self.padding != Padding::Forbidden {
+                (k..8).for_each(|_| base32.push('='));
+            }
+        });
+        base32
+    }
+
+    pub fn decode(&self, base32: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::with_capacity(5 * (base32.len() / 8));
+        let mut group = 0u64;
         let mut k = 0;
-        while k < 4 {
-            i += 1;
-            match iter.next() {
-                Some(b) => {
-                    if b == b'=' {
-                        // The character is a padding character, so assume we're at the end.
-                        break;
-                    }
-                    let b = BASE64_DECODE[usize::try_from(b).unwrap()];
-                    if b == 0xFF {
-                        // The character is an invalid hex character.
-                        return None;
-                    }
-                    triple += u32::from(b) << ((3 - k) * 6);
+        let mut padding = 0;
+        let mut padding_offset = 0;
+        for (i, b) in base32.bytes().enumerate() {
+            if b == b'=' {
+                if self.padding == Padding::Forbidden || k == 0 || k + padding == 8 {
+                    return Err(DecodeError::InvalidPadding(i));
                 }
-                // Not enough characters in the base64 string.
-                None => return None,
+                if padding == 0 {
+                    padding_offset = i;
+                }
+                padding += 1;
+                continue;
+            }
+
+            if padding > 0 {
+                return Err(DecodeError::InvalidPadding(i));
             }
+
+            let b = self.decode[usize::from(b)];
+            if b == 0xFF {
+                return Err(DecodeError::InvalidCharacter(i));
+            }
+
+            group = (group << 5) | u64::from(b);
             k += 1;
+            if k == 8 {
+                bytes.extend_from_slice(&group.to_be_bytes()[3..8]);
+                group = 0;
+                k = 0;
+            }
         }
 
-        let mut j = 0;
-        while j < k - 1 {
-            bytes.push(u8::try_from((triple >> ((2 - j) * 8)) % 256).unwrap());
-            j += 1;
+        if k != 0 {
+            // Only these partial group lengths correspond to a whole number of bytes.
+            let n = match k {
+                2 => 1,
+                4 => 2,
+                5 => 3,
+                7 => 4,
+                _ => return Err(DecodeError::InvalidLength(base32.len())),
+            };
+            if padding == 0 && self.padding == Padding::Required {
+                return Err(DecodeError::InvalidPadding(base32.len()));
+            }
+            if padding != 0 && k + padding != 8 {
+                return Err(DecodeError::InvalidPadding(padding_offset));
+            }
+
+            let group = group << (5 * (8 - k));
+            bytes.extend_from_slice(&group.to_be_bytes()[3..3 + n]);
         }
-        if j != 3 {
-            // Break early because we found the padding.
-            break;
+
+        Ok(bytes)
+    }
+}
+
+#[must_use]
+pub fn bytes_to_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut base58 = "1".repeat(zeros);
+    if zeros < bytes.len() {
+        BigUint::from_bytes_be(&bytes[zeros..])
+            .to_radix_be(58)
+            .into_iter()
+            .for_each(|d| base58.push(char::from(BASE58_ENCODE[usize::from(d)])));
+    }
+    base58
+}
+
+pub fn base58_to_bytes(base58: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut digits = Vec::with_capacity(base58.len());
+    for (i, b) in base58.bytes().enumerate() {
+        match BASE58_ENCODE.iter().position(|&c| c == b) {
+            Some(d) => digits.push(u8::try_from(d).unwrap()),
+            None => return Err(DecodeError::InvalidCharacter(i)),
         }
     }
 
-    Some(bytes)
+    // Every leading '1' encodes a leading zero byte.
+    let zeros = digits.iter().take_while(|&&d| d == 0).count();
+    let mut bytes = vec![0; zeros];
+    if zeros < digits.len() {
+        bytes.extend_from_slice(
+            &BigUint::from_radix_be(&digits[zeros..], 58)
+                .unwrap()
+                .to_bytes_be(),
+        );
+    }
+    Ok(bytes)
+}
+
+fn base58_checksum(payload: &[u8]) -> [u8; 4] {
+    let mut hash = [0; 32];
+    SHA256::default().hash(payload, &mut hash);
+    let mut hash_ = [0; 32];
+    SHA256::default().hash(&hash, &mut hash_);
+    hash_[0..4].try_into().unwrap()
+}
+
+#[must_use]
+pub fn bytes_to_base58check(payload: &[u8]) -> String {
+    bytes_to_base58(&[payload, &base58_checksum(payload)].concat())
+}
+
+pub fn base58check_to_bytes(base58: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut bytes = base58_to_bytes(base58)?;
+    if bytes.len() < 4 {
+        return Err(DecodeError::InvalidLength(base58.len()));
+    }
+
+    let checksum = bytes.split_off(bytes.len() - 4);
+    if checksum != base58_checksum(&bytes) {
+        return Err(DecodeError::InvalidChecksum);
+    }
+    Ok(bytes)
+}
+
+// Base85 encodes every 4 bytes as 5 digits, most significant first.
+fn encode_base85<F>(bytes: &[u8], digit: F, base85: &mut String)
+where
+    F: Fn(u32) -> char,
+{
+    let mut word = [0; 4];
+    word[..bytes.len()].copy_from_slice(bytes);
+    let mut word = u32::from_be_bytes(word);
+    let mut digits = [0; 5];
+    (0..5).rev().for_each(|j| {
+        digits[j] = word % 85;
+        word /= 85;
+    });
+    // A partial group is padded with zeros, and only the significant digits are kept.
+    digits[..=bytes.len()]
+        .iter()
+        .for_each(|&d| base85.push(digit(d)));
+}
+
+fn decode_base85(digits: &[u32], offset: usize, bytes: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let mut padded = [84; 5];
+    padded[..digits.len()].copy_from_slice(digits);
+    let word = padded
+        .iter()
+        .try_fold(0u32, |word, &d| word.checked_mul(85)?.checked_add(d))
+        .ok_or(DecodeError::InvalidCharacter(offset))?;
+    bytes.extend_from_slice(&word.to_be_bytes()[..digits.len() - 1]);
+    Ok(())
+}
+
+#[must_use]
+pub fn bytes_to_ascii85(bytes: &[u8]) -> String {
+    let mut ascii85 = String::with_capacity(5 * bytes.len().div_ceil(4));
+    bytes.chunks(4).for_each(|chunk| {
+        if chunk == [0; 4] {
+            ascii85.push('z');
+        } else {
+            encode_base85(
+                chunk,
+                |d| char::from(b'!' + u8::try_from(d).unwrap()),
+                &mut ascii85,
+            );
+        }
+    });
+    ascii85
+}
+
+pub fn ascii85_to_bytes(ascii85: &str) -> Result<Vec<u8>, DecodeError> {
+    // The Adobe delimiters are optional.
+    let (start, end) = match (ascii85.find("<~"), ascii85.rfind("~>")) {
+        (Some(start), Some(end)) if start + 2 <= end => (start + 2, end),
+        _ => (0, ascii85.len()),
+    };
+    let mut bytes = Vec::with_capacity(4 * (ascii85.len() / 5));
+    let mut digits = Vec::with_capacity(5);
+    let mut offset = start;
+    for (i, b) in ascii85[start..end].bytes().enumerate() {
+        let i = start + i;
+        if b.is_ascii_whitespace() {
+            continue;
+        }
+
+        if b == b'z' {
+            if !digits.is_empty() {
+                return Err(DecodeError::InvalidCharacter(i));
+            }
+            bytes.extend_from_slice(&[0; 4]);
+            continue;
+        }
+
+        if !(b'!'..=b'u').contains(&b) {
+            return Err(DecodeError::InvalidCharacter(i));
+        }
+
+        if digits.is_empty() {
+            offset = i;
+        }
+        digits.push(u32::from(b - b'!'));
+        if digits.len() == 5 {
+            decode_base85(&digits, offset, &mut bytes)?;
+            digits.clear();
+        }
+    }
+
+    match digits.len() {
+        0 => {}
+        1 => return Err(DecodeError::InvalidLength(end)),
+        _ => decode_base85(&digits, offset, &mut bytes)?,
+    }
+    Ok(bytes)
+}
+
+#[must_use]
+pub fn bytes_to_z85(bytes: &[u8]) -> Option<String> {
+    // Z85 doesn't define partial groups.
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut z85 = String::with_capacity(5 * bytes.len() / 4);
+    bytes.chunks(4).for_each(|chunk| {
+        encode_base85(
+            chunk,
+            |d| char::from(Z85_ENCODE[usize::try_from(d).unwrap()]),
+            &mut z85,
+        );
+    });
+    Some(z85)
+}
+
+pub fn z85_to_bytes(z85: &str) -> Result<Vec<u8>, DecodeError> {
+    if !z85.len().is_multiple_of(5) {
+        return Err(DecodeError::InvalidLength(z85.len()));
+    }
+
+    let mut bytes = Vec::with_capacity(4 * (z85.len() / 5));
+    let mut digits = Vec::with_capacity(5);
+    for (i, b) in z85.bytes().enumerate() {
+        match Z85_ENCODE.iter().position(|&c| c == b) {
+            Some(d) => digits.push(u32::try_from(d).unwrap()),
+            None => return Err(DecodeError::InvalidCharacter(i)),
+        }
+        if digits.len() == 5 {
+            decode_base85(&digits, i - 4, &mut bytes)?;
+            digits.clear();
+        }
+    }
+    Ok(bytes)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    UpperHex,
+    Base32,
+    Base32Hex,
+    Base58,
+    Base64,
+    Base64UrlSafe,
+    Z85,
+    Ascii85,
+}
+
+impl Encoding {
+    pub fn decode(&self, input: &str) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            // hex_to_bytes accepts both cases, but doesn't report offsets.
+            Encoding::Hex | Encoding::UpperHex => {
+                match input.bytes().position(|b| !b.is_ascii_hexdigit()) {
+                    Some(i) => Err(DecodeError::InvalidCharacter(i)),
+                    None => hex_to_bytes(input).ok_or(DecodeError::InvalidLength(input.len())),
+                }
+            }
+            Encoding::Base32 => Base32::new_standard().decode(input),
+            Encoding::Base32Hex => Base32::new_hex().decode(input),
+            Encoding::Base58 => base58_to_bytes(input),
+            Encoding::Base64 => Base64::new_standard().decode(input),
+            Encoding::Base64UrlSafe => {
+                Base64::new(Alphabet::UrlSafe, Padding::Optional, None, false).decode(input)
+            }
+            Encoding::Z85 => z85_to_bytes(input),
+            Encoding::Ascii85 => ascii85_to_bytes(input),
+        }
+    }
+}
+
+// Returns the encodings the input decodes under, from the smallest alphabet to the largest.
+#[must_use]
+pub fn detect_encodings(input: &str) -> Vec<Encoding> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Vec::new();
+    }
+
+    let hex = if input.bytes().any(|b| b.is_ascii_lowercase()) {
+        Encoding::Hex
+    } else {
+        Encoding::UpperHex
+    };
+    [
+        hex,
+        Encoding::Base32,
+        Encoding::Base32Hex,
+        Encoding::Base58,
+        Encoding::Base64,
+        Encoding::Base64UrlSafe,
+        Encoding::Z85,
+        Encoding::Ascii85,
+    ]
+    .into_iter()
+    // Base32 is only ever uppercase in practice, even though we decode lowercase.
+    .filter(|e| {
+        !matches!(e, Encoding::Base32 | Encoding::Base32Hex)
+            || !input.bytes().any(|b| b.is_ascii_lowercase())
+    })
+    .filter(|e| e.decode(input).is_ok())
+    .collect()
+}
+
+fn hexdump_hex(block: &[u8], block_size: usize) -> String {
+    let mut hex = block
+        .iter()
+        .map(|&b| bytes_to_hex(&[b]))
+        .collect::<Vec<String>>()
+        .join(" ");
+    // Pad partial blocks so the ASCII gutter stays aligned.
+    (block.len()..block_size).for_each(|_| hex.push_str("   "));
+    hex
+}
+
+fn hexdump_ascii(block: &[u8], block_size: usize) -> String {
+    let mut ascii = block
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                char::from(b)
+            } else {
+                '.'
+            }
+        })
+        .collect::<String>();
+    (block.len()..block_size).for_each(|_| ascii.push(' '));
+    ascii
+}
+
+// Renders one block per line, marking blocks that occur more than once with the index of their first occurrence.
+#[must_use]
+pub fn hexdump(bytes: &[u8], block_size: usize) -> String {
+    // A block size of zero renders everything on a single row.
+    let block_size = if block_size == 0 {
+        bytes.len().max(1)
+    } else {
+        block_size
+    };
+    let repeated = repeated_blocks(bytes, block_size);
+    let mut hexdump = String::new();
+    bytes.chunks(block_size).enumerate().for_each(|(i, block)| {
+        hexdump.push_str(&format!(
+            "{:08x}  {}  |{}|",
+            i * block_size,
+            hexdump_hex(block, block_size),
+            hexdump_ascii(block, block_size)
+        ));
+        if let Some(Some(first)) = repeated.get(i) {
+            hexdump.push_str(&format!(" = block {first}"));
+        }
+        hexdump.push('\n');
+    });
+    hexdump
+}
+
+// Renders both buffers block by block, showing blocks that differ on two lines with the changed bytes marked below.
+#[must_use]
+pub fn hexdiff(a: &[u8], b: &[u8], block_size: usize) -> String {
+    let mut hexdiff = String::new();
+    let len = a.len().max(b.len());
+    let block_size = if block_size == 0 {
+        len.max(1)
+    } else {
+        block_size
+    };
+    (0..len).step_by(block_size).for_each(|offset| {
+        let block_a = &a[offset.min(a.len())..(offset + block_size).min(a.len())];
+        let block_b = &b[offset.min(b.len())..(offset + block_size).min(b.len())];
+        if block_a == block_b {
+            hexdiff.push_str(&format!(
+                "{offset:08x}    {}  |{}|\n",
+                hexdump_hex(block_a, block_size),
+                hexdump_ascii(block_a, block_size)
+            ));
+            return;
+        }
+
+        hexdiff.push_str(&format!(
+            "{offset:08x}  - {}  |{}|\n",
+            hexdump_hex(block_a, block_size),
+            hexdump_ascii(block_a, block_size)
+        ));
+        hexdiff.push_str(&format!(
+            "          + {}  |{}|\n",
+            hexdump_hex(block_b, block_size),
+            hexdump_ascii(block_b, block_size)
+        ));
+        let changed = (0..block_size)
+            .map(|j| block_a.get(j) != block_b.get(j))
+            .collect::<Vec<bool>>();
+        let mut markers = " ".repeat(12);
+        changed.iter().enumerate().for_each(|(j, &c)| {
+            if j > 0 {
+                markers.push(' ');
+            }
+            markers.push_str(if c { "^^" } else { "  " });
+        });
+        markers.push_str("   ");
+        changed
+            .iter()
+            .for_each(|&c| markers.push(if c { '^' } else { ' ' }));
+        hexdiff.push_str(markers.trim_end());
+        hexdiff.push('\n');
+    });
+    hexdiff
 }
@@ -0,0 +1,139 @@
+use crate::shared::lattice::lll;
+use crate::shared::mod_inv;
+use crate::shared::polynomial::{degree, evaluate, integer_roots, padded_polynomial};
+use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+// x^shift * n^(m - i) * f^i as integer coefficients, lowest degree first.
+fn shift_polynomial(f: &[BigInt], n: &BigInt, i: u32, m: u32, shift: usize) -> Vec<BigInt> {
+    let mut poly = vec![BigInt::one()];
+    for _ in 0..i {
+        let mut product = vec![BigInt::zero(); poly.len() + f.len() - 1];
+        poly.iter().enumerate().for_each(|(j, a)| {
+            f.iter()
+                .enumerate()
+                .for_each(|(k, b)| product[j + k] += a * b);
+        });
+        poly = product;
+    }
+    let n_power = n.pow(m - i);
+    let mut shifted = vec![BigInt::zero(); shift];
+    shifted.extend(poly.into_iter().map(|c| c * &n_power));
+    shifted
+}
+
+// Howgrave-Graham's version of Coppersmith's method: the roots x0 with |x0| <= bound of poly mod some unknown divisor
+// b >= n^beta of n. poly has degree delta and is made monic first. The lattice has the rows x^j * n^(m - i) * f^i for
+// i < m and j < delta, and x^j * f^m for j < t, evaluated at bound * x. Short rows are polynomials that have x0 as a
+// root over the integers, which works as long as roughly bound < n^(beta^2 / delta). m and t trade speed for range:
+// t = 0 is best for beta = 1, and t = delta * m * (1 / beta - 1) in general.
+// Returns every candidate root for which poly(x0) shares a factor with n.
+#[must_use]
+pub fn small_roots(poly: &[BigUint], n: &BigUint, bound: &BigUint, m: u32, t: u32) -> Vec<BigInt> {
+    let Some(delta) = poly
+        .iter()
+        .rposition(|c| !(c % n).is_zero())
+        .filter(|&delta| delta > 0)
+    else {
+        return Vec::new();
+    };
+    let Some(lead_inv) = mod_inv(&poly[delta], n) else {
+        return Vec::new();
+    };
+    let f = poly[..=delta]
+        .iter()
+        .map(|c| (c * &lead_inv).mod_floor(n).to_bigint().unwrap())
+        .collect::<Vec<BigInt>>();
+    let n_ = &n.to_bigint().unwrap();
+
+    let mut shifts = (0..m)
+        .flat_map(|i| (0..delta).map(move |j| (i, j)))
+        .map(|(i, j)| shift_polynomial(&f, n_, i, m, j))
+        .collect::<Vec<Vec<BigInt>>>();
+    shifts.extend((0..usize::try_from(t).unwrap()).map(|j| shift_polynomial(&f, n_, m, m, j)));
+    let dimension = shifts.len();
+    let x = &bound.to_bigint().unwrap();
+    let basis = shifts
+        .into_iter()
+        .map(|mut poly| {
+            poly.resize(dimension, BigInt::zero());
+            poly.iter()
+                .enumerate()
+                .map(|(k, c)| c * x.pow(u32::try_from(k).unwrap()))
+                .collect()
+        })
+        .collect::<Vec<Vec<BigInt>>>();
+
+    let Some(reduced) = lll(&basis) else {
+        return Vec::new();
+    };
+    let mut roots = Vec::new();
+    for row in reduced {
+        let h = row
+            .iter()
+            .enumerate()
+            .map(|(k, c)| c / x.pow(u32::try_from(k).unwrap()))
+            .collect::<Vec<BigInt>>();
+        if degree(&h).is_none_or(|degree| degree == 0) {
+            continue;
+        }
+
+        integer_roots(&h, &-x, x)
+            .into_iter()
+            .filter(|x0| !evaluate(&f, x0).gcd(n_).is_one())
+            .for_each(|x0| roots.push(x0));
+        if !roots.is_empty() {
+            break;
+        }
+    }
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+// m = known + x for an unknown x < 2^unknown_bits, encrypted with a small e, like a fixed message with a short secret
+// at the end. The unknown part can be almost (bits of n) / e bits long.
+#[must_use]
+pub fn stereotyped_message(
+    public_key: &(BigUint, BigUint),
+    c: &BigUint,
+    known: &BigUint,
+    unknown_bits: u64,
+    m: u32,
+) -> Option<BigUint> {
+    let (n, e) = public_key;
+    let poly = padded_polynomial(u32::try_from(e).ok()?, n, c, &BigUint::one(), known);
+    let bound = BigUint::one() << unknown_bits;
+    small_roots(&poly, n, &bound, m, 0)
+        .into_iter()
+        .filter_map(|x| x.to_biguint())
+        .map(|x| known + x)
+        .find(|message| &message.modpow(e, n) == c)
+}
+
+// Factors n when all but the low unknown_bits of p are known: p = high + x is a root of x + high mod p, and p is about
+// n^(1/2), so this works when unknown_bits is a bit less than a quarter of the bits of n.
+#[must_use]
+pub fn factor_with_high_bits_of_p(
+    n: &BigUint,
+    high: &BigUint,
+    unknown_bits: u64,
+    m: u32,
+) -> Option<(BigUint, BigUint)> {
+    let poly = [high.clone(), BigUint::one()];
+    let bound = BigUint::one() << unknown_bits;
+    small_roots(&poly, n, &bound, m, m)
+        .into_iter()
+        .filter_map(|x| x.to_biguint())
+        .map(|x| high + x)
+        .find(|p| *p > BigUint::one() && p < n && (n % p).is_zero())
+        .map(|p| {
+            let q = n / &p;
+            if p <= q {
+                (p, q)
+            } else {
+                (q, p)
+            }
+        })
+}
@@ -0,0 +1,72 @@
+use crate::shared::keystream::Keystream;
+use crate::shared::xor::{english_floor, score};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    // The crib is assumed to be the plaintext of this ciphertext at this offset.
+    pub ct_index: usize,
+    pub offset: usize,
+    pub crib: Vec<u8>,
+    // The resulting plaintext fragments of the other ciphertexts that are long enough.
+    pub fragments: Vec<(usize, Vec<u8>)>,
+    pub score: f64,
+}
+
+impl Candidate {
+    pub fn commit<T: AsRef<[u8]>>(&self, keystream: &mut Keystream, cts: &[T]) {
+        keystream.apply_crib(cts[self.ct_index].as_ref(), self.offset, &self.crib);
+    }
+}
+
+#[must_use]
+pub fn combine(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+// Slides the crib across every ciphertext and scores the fragments it reveals in the other ciphertexts, best first.
+// Positions where any fragment isn't printable are discarded.
+#[must_use]
+pub fn drag<T: AsRef<[u8]>>(cts: &[T], crib: &[u8]) -> Vec<Candidate> {
+    let floor = english_floor();
+    let mut candidates = Vec::new();
+    cts.iter().enumerate().for_each(|(i, ct)| {
+        let ct = ct.as_ref();
+        (0..(ct.len() + 1).saturating_sub(crib.len())).for_each(|offset| {
+            let mut fragments = Vec::new();
+            let mut total = 0.0;
+            for (j, other) in cts.iter().enumerate() {
+                let other = other.as_ref();
+                if j == i || other.len() < offset + crib.len() {
+                    continue;
+                }
+
+                let mut fragment = combine(
+                    &ct[offset..offset + crib.len()],
+                    &other[offset..offset + crib.len()],
+                );
+                fragment.iter_mut().zip(crib).for_each(|(f, c)| *f ^= c);
+                match score(&fragment, floor) {
+                    Some(score) => total += score,
+                    None => return,
+                }
+                fragments.push((j, fragment));
+            }
+
+            if fragments.is_empty() {
+                return;
+            }
+
+            // Average, so offsets covered by fewer ciphertexts aren't favoured.
+            let score = total / f64::from(u32::try_from(fragments.len()).unwrap());
+            candidates.push(Candidate {
+                ct_index: i,
+                offset,
+                crib: crib.to_owned(),
+                fragments,
+                score,
+            });
+        });
+    });
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
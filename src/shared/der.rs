@@ -0,0 +1,373 @@
+use crate::shared::rsa::PrivateKey;
+use num_bigint::{BigInt, BigUint, Sign};
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OBJECT_IDENTIFIER: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+
+pub const RSA_ENCRYPTION_OID: &[u64] = &[1, 2, 840, 113_549, 1, 1, 1];
+pub const DSA_OID: &[u64] = &[1, 2, 840, 10040, 4, 1];
+
+// Bounds the recursion on attacker-controlled input.
+const MAX_DEPTH: usize = 32;
+
+// Offsets are byte offsets into the DER being decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    Truncated(usize),
+    InvalidLength(usize),
+    InvalidContents(usize),
+    TrailingData(usize),
+    TooDeeplyNested(usize),
+    UnexpectedStructure,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Der {
+    Integer(BigInt),
+    BitString(u8, Vec<u8>),
+    OctetString(Vec<u8>),
+    Null,
+    ObjectIdentifier(Vec<u64>),
+    Sequence(Vec<Der>),
+    Other(u8, Vec<u8>),
+}
+
+impl Der {
+    #[must_use]
+    pub fn unsigned(i: &BigUint) -> Self {
+        Der::Integer(BigInt::from_biguint(Sign::Plus, i.clone()))
+    }
+
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let (tag, contents) = match self {
+            Der::Integer(i) => (TAG_INTEGER, i.to_signed_bytes_be()),
+            Der::BitString(unused_bits, bytes) => {
+                let mut contents = Vec::with_capacity(1 + bytes.len());
+                contents.push(*unused_bits);
+                contents.extend_from_slice(bytes);
+                (TAG_BIT_STRING, contents)
+            }
+            Der::OctetString(bytes) => (TAG_OCTET_STRING, bytes.clone()),
+            Der::Null => (TAG_NULL, Vec::new()),
+            Der::ObjectIdentifier(arcs) => {
+                assert!(arcs.len() >= 2 && arcs[0] <= 2);
+                let mut contents = Vec::new();
+                encode_base128(&mut contents, arcs[0] * 40 + arcs[1]);
+                arcs[2..]
+                    .iter()
+                    .for_each(|&arc| encode_base128(&mut contents, arc));
+                (TAG_OBJECT_IDENTIFIER, contents)
+            }
+            Der::Sequence(elements) => (
+                TAG_SEQUENCE,
+                elements.iter().flat_map(Der::encode).collect(),
+            ),
+            Der::Other(tag, contents) => (*tag, contents.clone()),
+        };
+
+        let mut encoded = Vec::with_capacity(contents.len() + 6);
+        encoded.push(tag);
+        if contents.len() < 0x80 {
+            encoded.push(u8::try_from(contents.len()).unwrap());
+        } else {
+            let length = contents.len().to_be_bytes();
+            let skip = length.iter().take_while(|&&b| b == 0).count();
+            encoded.push(0x80 | u8::try_from(length.len() - skip).unwrap());
+            encoded.extend_from_slice(&length[skip..]);
+        }
+        encoded.extend_from_slice(&contents);
+        encoded
+    }
+
+    pub fn decode(der: &[u8]) -> Result<Self, Error> {
+        let (decoded, end) = Self::decode_at(der, 0, 0)?;
+        if end != der.len() {
+            return Err(Error::TrailingData(end));
+        }
+        Ok(decoded)
+    }
+
+    // Decodes a single element starting at offset, returning it and the offset just past it.
+    fn decode_at(der: &[u8], offset: usize, depth: usize) -> Result<(Self, usize), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::TooDeeplyNested(offset));
+        }
+        let tag = *der.get(offset).ok_or(Error::Truncated(offset))?;
+        let first = *der.get(offset + 1).ok_or(Error::Truncated(offset + 1))?;
+        let (length, start) = if first < 0x80 {
+            (usize::from(first), offset + 2)
+        } else {
+            let n = usize::from(first & 0x7F);
+            // Indefinite lengths aren't allowed in DER.
+            if n == 0 || n > std::mem::size_of::<usize>() {
+                return Err(Error::InvalidLength(offset + 1));
+            }
+            let bytes = der
+                .get(offset + 2..offset + 2 + n)
+                .ok_or(Error::Truncated(offset + 2))?;
+            let length = bytes
+                .iter()
+                .fold(0, |length, &b| (length << 8) | usize::from(b));
+            // DER requires the shortest possible length encoding.
+            if bytes[0] == 0 || length < 0x80 {
+                return Err(Error::InvalidLength(offset + 1));
+            }
+            (length, offset + 2 + n)
+        };
+        let end = start
+            .checked_add(length)
+            .ok_or(Error::InvalidLength(offset + 1))?;
+        let contents = der.get(start..end).ok_or(Error::Truncated(der.len()))?;
+
+        let decoded = match tag {
+            TAG_INTEGER => {
+                if contents.is_empty()
+                    || (contents.len() > 1
+                        && ((contents[0] == 0x00 && contents[1] < 0x80)
+                            || (contents[0] == 0xFF && contents[1] >= 0x80)))
+                {
+                    return Err(Error::InvalidContents(start));
+                }
+                Der::Integer(BigInt::from_signed_bytes_be(contents))
+            }
+            TAG_BIT_STRING => {
+                if contents.is_empty() || contents[0] > 7 {
+                    return Err(Error::InvalidContents(start));
+                }
+                Der::BitString(contents[0], contents[1..].to_vec())
+            }
+            TAG_OCTET_STRING => Der::OctetString(contents.to_vec()),
+            TAG_NULL => {
+                if !contents.is_empty() {
+                    return Err(Error::InvalidContents(start));
+                }
+                Der::Null
+            }
+            TAG_OBJECT_IDENTIFIER => {
+                let mut arcs = Vec::new();
+                let mut arc = 0u64;
+                for (i, &b) in contents.iter().enumerate() {
+                    if (arc == 0 && b == 0x80) || arc >> 57 != 0 {
+                        return Err(Error::InvalidContents(start + i));
+                    }
+                    arc = (arc << 7) | u64::from(b & 0x7F);
+                    if b & 0x80 == 0 {
+                        if arcs.is_empty() {
+                            let first = (arc / 40).min(2);
+                            arcs.push(first);
+                            arcs.push(arc - first * 40);
+                        } else {
+                            arcs.push(arc);
+                        }
+                        arc = 0;
+                    }
+                }
+                if arcs.is_empty() || contents[contents.len() - 1] & 0x80 != 0 {
+                    return Err(Error::InvalidContents(start));
+                }
+                Der::ObjectIdentifier(arcs)
+            }
+            TAG_SEQUENCE => {
+                let mut elements = Vec::new();
+                let mut i = start;
+                while i < end {
+                    let (element, next) = Self::decode_at(&der[..end], i, depth + 1)?;
+                    elements.push(element);
+                    i = next;
+                }
+                Der::Sequence(elements)
+            }
+            _ => Der::Other(tag, contents.to_vec()),
+        };
+        Ok((decoded, end))
+    }
+
+    fn as_unsigned(&self) -> Result<BigUint, Error> {
+        match self {
+            Der::Integer(i) => i.to_biguint().ok_or(Error::UnexpectedStructure),
+            _ => Err(Error::UnexpectedStructure),
+        }
+    }
+
+    fn as_sequence(&self, len: usize) -> Result<&[Der], Error> {
+        match self {
+            Der::Sequence(elements) if elements.len() == len => Ok(elements),
+            _ => Err(Error::UnexpectedStructure),
+        }
+    }
+}
+
+fn encode_base128(encoded: &mut Vec<u8>, mut value: u64) {
+    let mut groups = vec![u8::try_from(value & 0x7F).unwrap()];
+    value >>= 7;
+    while value > 0 {
+        groups.push(0x80 | u8::try_from(value & 0x7F).unwrap());
+        value >>= 7;
+    }
+    encoded.extend(groups.iter().rev());
+}
+
+fn decode_unsigned_sequence<const N: usize>(der: &[u8]) -> Result<[BigUint; N], Error> {
+    let decoded = Der::decode(der)?;
+    let elements = decoded.as_sequence(N)?;
+    let mut integers: [BigUint; N] = std::array::from_fn(|_| BigUint::default());
+    for (integer, element) in integers.iter_mut().zip(elements) {
+        *integer = element.as_unsigned()?;
+    }
+    Ok(integers)
+}
+
+#[must_use]
+pub fn encode_rsa_public_key(public_key: &(BigUint, BigUint)) -> Vec<u8> {
+    let (n, e) = public_key;
+    Der::Sequence(vec![Der::unsigned(n), Der::unsigned(e)]).encode()
+}
+
+pub fn decode_rsa_public_key(der: &[u8]) -> Result<(BigUint, BigUint), Error> {
+    let [n, e] = decode_unsigned_sequence(der)?;
+    Ok((n, e))
+}
+
+#[must_use]
+pub fn encode_rsa_private_key(private_key: &PrivateKey) -> Vec<u8> {
+    Der::Sequence(vec![
+        Der::unsigned(&BigUint::default()),
+        Der::unsigned(&private_key.n),
+        Der::unsigned(&private_key.e),
+        Der::unsigned(&private_key.d),
+        Der::unsigned(&private_key.p),
+        Der::unsigned(&private_key.q),
+        Der::unsigned(&private_key.dp),
+        Der::unsigned(&private_key.dq),
+        Der::unsigned(&private_key.qinv),
+    ])
+    .encode()
+}
+
+pub fn decode_rsa_private_key(der: &[u8]) -> Result<PrivateKey, Error> {
+    let [version, n, e, d, p, q, dp, dq, qinv] = decode_unsigned_sequence(der)?;
+    // Only two-prime keys (version 0) are supported.
+    if version != BigUint::default() {
+        return Err(Error::UnexpectedStructure);
+    }
+    Ok(PrivateKey {
+        n,
+        e,
+        d,
+        p,
+        q,
+        dp,
+        dq,
+        qinv,
+    })
+}
+
+#[must_use]
+pub fn encode_dsa_parameters(p: &BigUint, q: &BigUint, g: &BigUint) -> Vec<u8> {
+    Der::Sequence(vec![Der::unsigned(p), Der::unsigned(q), Der::unsigned(g)]).encode()
+}
+
+pub fn decode_dsa_parameters(der: &[u8]) -> Result<(BigUint, BigUint, BigUint), Error> {
+    let [p, q, g] = decode_unsigned_sequence(der)?;
+    Ok((p, q, g))
+}
+
+// This is the OpenSSL "DSA PRIVATE KEY" format: version, p, q, g, y, and x.
+#[must_use]
+pub fn encode_dsa_private_key(
+    p: &BigUint,
+    q: &BigUint,
+    g: &BigUint,
+    y: &BigUint,
+    x: &BigUint,
+) -> Vec<u8> {
+    Der::Sequence(vec![
+        Der::unsigned(&BigUint::default()),
+        Der::unsigned(p),
+        Der::unsigned(q),
+        Der::unsigned(g),
+        Der::unsigned(y),
+        Der::unsigned(x),
+    ])
+    .encode()
+}
+
+#[allow(clippy::type_complexity)]
+pub fn decode_dsa_private_key(
+    der: &[u8],
+) -> Result<(BigUint, BigUint, BigUint, BigUint, BigUint), Error> {
+    let [version, p, q, g, y, x] = decode_unsigned_sequence(der)?;
+    if version != BigUint::default() {
+        return Err(Error::UnexpectedStructure);
+    }
+    Ok((p, q, g, y, x))
+}
+
+#[must_use]
+pub fn encode_dsa_signature(r: &BigUint, s: &BigUint) -> Vec<u8> {
+    Der::Sequence(vec![Der::unsigned(r), Der::unsigned(s)]).encode()
+}
+
+pub fn decode_dsa_signature(der: &[u8]) -> Result<(BigUint, BigUint), Error> {
+    let [r, s] = decode_unsigned_sequence(der)?;
+    Ok((r, s))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicKey {
+    Rsa(BigUint, BigUint),
+    Dsa(BigUint, BigUint, BigUint, BigUint),
+}
+
+#[must_use]
+pub fn encode_subject_public_key_info(public_key: &PublicKey) -> Vec<u8> {
+    let (algorithm, subject_public_key) = match public_key {
+        PublicKey::Rsa(n, e) => (
+            Der::Sequence(vec![
+                Der::ObjectIdentifier(RSA_ENCRYPTION_OID.to_vec()),
+                Der::Null,
+            ]),
+            encode_rsa_public_key(&(n.clone(), e.clone())),
+        ),
+        PublicKey::Dsa(p, q, g, y) => (
+            Der::Sequence(vec![
+                Der::ObjectIdentifier(DSA_OID.to_vec()),
+                Der::Sequence(vec![Der::unsigned(p), Der::unsigned(q), Der::unsigned(g)]),
+            ]),
+            Der::unsigned(y).encode(),
+        ),
+    };
+    Der::Sequence(vec![algorithm, Der::BitString(0, subject_public_key)]).encode()
+}
+
+pub fn decode_subject_public_key_info(der: &[u8]) -> Result<PublicKey, Error> {
+    let decoded = Der::decode(der)?;
+    let elements = decoded.as_sequence(2)?;
+    let algorithm = elements[0].as_sequence(2)?;
+    let subject_public_key = match &elements[1] {
+        Der::BitString(0, bytes) => bytes,
+        _ => return Err(Error::UnexpectedStructure),
+    };
+    match &algorithm[0] {
+        Der::ObjectIdentifier(oid) if oid == RSA_ENCRYPTION_OID => {
+            let (n, e) = decode_rsa_public_key(subject_public_key)?;
+            Ok(PublicKey::Rsa(n, e))
+        }
+        Der::ObjectIdentifier(oid) if oid == DSA_OID => {
+            let parameters = algorithm[1].as_sequence(3)?;
+            let y = Der::decode(subject_public_key)?.as_unsigned()?;
+            Ok(PublicKey::Dsa(
+                parameters[0].as_unsigned()?,
+                parameters[1].as_unsigned()?,
+                parameters[2].as_unsigned()?,
+                y,
+            ))
+        }
+        _ => Err(Error::UnexpectedStructure),
+    }
+}
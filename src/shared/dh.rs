@@ -1,13 +1,38 @@
 use crate::shared::hmac::hmac;
 use crate::shared::mod_sub;
+use crate::shared::number_theory::generate_safe_prime_with_rng;
 use crate::shared::sha256::SHA256;
 use num_bigint::{BigUint, RandBigInt};
 use num_traits::Zero;
 use rand::RngCore;
 
+// A safe prime p = 2q + 1, with g generating the subgroup of prime order q.
+#[must_use]
+pub fn generate_parameters(bits: u64) -> (BigUint, BigUint) {
+    generate_parameters_with_rng(bits, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_parameters_with_rng<R: RngCore + ?Sized>(
+    bits: u64,
+    rng: &mut R,
+) -> (BigUint, BigUint) {
+    let p = generate_safe_prime_with_rng(bits, rng);
+    (p, BigUint::from(4u8))
+}
+
 #[must_use]
 pub fn generate_keypair(p: &BigUint, g: &BigUint) -> (BigUint, BigUint) {
-    let private = rand::thread_rng().gen_biguint_below(p);
+    generate_keypair_with_rng(p, g, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_keypair_with_rng<R: RngCore + ?Sized>(
+    p: &BigUint,
+    g: &BigUint,
+    rng: &mut R,
+) -> (BigUint, BigUint) {
+    let private = rng.gen_biguint_below(p);
     let public = g.modpow(&private, p);
     (private, public)
 }
@@ -29,13 +54,38 @@ pub fn srp(
     SK: &mut [u8; 32],
     Smac: &mut [u8; 32],
 ) {
-    let mut rng = rand::thread_rng();
+    srp_with_rng(
+        N,
+        g,
+        k,
+        send_A_0,
+        CK,
+        Cmac,
+        SK,
+        Smac,
+        &mut rand::thread_rng(),
+    );
+}
+
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+pub fn srp_with_rng<R: RngCore + ?Sized>(
+    N: &BigUint,
+    g: &BigUint,
+    k: &BigUint,
+    send_A_0: bool,
+    CK: &mut [u8; 32],
+    Cmac: &mut [u8; 32],
+    SK: &mut [u8; 32],
+    Smac: &mut [u8; 32],
+    rng: &mut R,
+) {
     let P = b"password";
 
     let CN = N;
     let Cg = g;
     let Ck = k;
-    let (ref Ca, mut CA) = generate_keypair(N, Cg);
+    let (ref Ca, mut CA) = generate_keypair_with_rng(N, Cg, rng);
     if send_A_0 {
         CA.set_zero();
     }
@@ -44,7 +94,7 @@ pub fn srp(
     let SN = N;
     let Sg = g;
     let Sk = k;
-    let (ref Sb, ref SB) = generate_keypair(N, Cg);
+    let (ref Sb, ref SB) = generate_keypair_with_rng(N, Cg, rng);
 
     let mut Ssalt = [0; 16];
     rng.fill_bytes(&mut Ssalt);
@@ -94,16 +144,27 @@ pub fn simplified_srp(
     Cmac: &mut [u8; 32],
     Smac: &mut [u8; 32],
 ) -> (BigUint, BigUint, BigUint) {
-    let mut rng = rand::thread_rng();
+    simplified_srp_with_rng(N, g, Ssalt, Cmac, Smac, &mut rand::thread_rng())
+}
+
+#[allow(non_snake_case)]
+pub fn simplified_srp_with_rng<R: RngCore + ?Sized>(
+    N: &BigUint,
+    g: &BigUint,
+    Ssalt: &mut [u8; 16],
+    Cmac: &mut [u8; 32],
+    Smac: &mut [u8; 32],
+    rng: &mut R,
+) -> (BigUint, BigUint, BigUint) {
     let P = b"password";
 
     let CN = N;
     let Cg = g;
-    let (ref Ca, CA) = generate_keypair(N, Cg);
+    let (ref Ca, CA) = generate_keypair_with_rng(N, Cg, rng);
 
     let SN = N;
     let Sg = g;
-    let (Sb, ref SB) = generate_keypair(N, Cg);
+    let (Sb, ref SB) = generate_keypair_with_rng(N, Cg, rng);
 
     rng.fill_bytes(Ssalt);
     let Ssalt: &[u8] = &*Ssalt;
@@ -1,19 +1,67 @@
+use crate::shared::number_theory::generate_dsa_primes_with_rng;
 use crate::shared::{mod_inv, mod_sub};
 use num_bigint::{BigUint, RandBigInt};
 use num_integer::Integer;
 use num_traits::One;
+use rand::RngCore;
+
+// Returns (p, q, g) with an l bit p and an n bit q.
+#[must_use]
+pub fn generate_parameters(l: u64, n: u64) -> (BigUint, BigUint, BigUint) {
+    generate_parameters_with_rng(l, n, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_parameters_with_rng<R: RngCore + ?Sized>(
+    l: u64,
+    n: u64,
+    rng: &mut R,
+) -> (BigUint, BigUint, BigUint) {
+    let (p, q) = generate_dsa_primes_with_rng(l, n, rng);
+    let exponent = &((&p - 1u8) / &q);
+    let mut h = BigUint::from(2u8);
+    loop {
+        let g = h.modpow(exponent, &p);
+        if !g.is_one() {
+            return (p, q, g);
+        }
+        h += 1u8;
+    }
+}
 
 #[must_use]
 pub fn generate_keypair(p: &BigUint, q: &BigUint, g: &BigUint) -> (BigUint, BigUint) {
-    let x = rand::thread_rng().gen_biguint_range(&BigUint::one(), q);
+    generate_keypair_with_rng(p, q, g, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_keypair_with_rng<R: RngCore + ?Sized>(
+    p: &BigUint,
+    q: &BigUint,
+    g: &BigUint,
+    rng: &mut R,
+) -> (BigUint, BigUint) {
+    let x = rng.gen_biguint_range(&BigUint::one(), q);
     let y = g.modpow(&x, p);
     (x, y)
 }
 
 #[must_use]
 pub fn sign(p: &BigUint, q: &BigUint, g: &BigUint, x: &BigUint, m: &BigUint) -> (BigUint, BigUint) {
+    sign_with_rng(p, q, g, x, m, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn sign_with_rng<R: RngCore + ?Sized>(
+    p: &BigUint,
+    q: &BigUint,
+    g: &BigUint,
+    x: &BigUint,
+    m: &BigUint,
+    rng: &mut R,
+) -> (BigUint, BigUint) {
     assert!(m < q);
-    let k = &rand::thread_rng().gen_biguint_range(&BigUint::one(), q);
+    let k = &rng.gen_biguint_range(&BigUint::one(), q);
     // Normally we should check that r and s are not zero here, but then challenge 45 doesn't work...
     let r = g.modpow(k, p).mod_floor(q);
     let s = (mod_inv(k, q).unwrap() * (m + x * &r)).mod_floor(q);
@@ -35,7 +83,9 @@ pub fn verify(
     let w = &mod_inv(s, q).unwrap();
     let u1 = &(m * w).mod_floor(q);
     let u2 = &(r * w).mod_floor(q);
-    let v = &(g.modpow(u1, p) * y.modpow(u2, p)).mod_floor(q);
+    let v = &(g.modpow(u1, p) * y.modpow(u2, p))
+        .mod_floor(p)
+        .mod_floor(q);
     v == r
 }
 
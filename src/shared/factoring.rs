@@ -0,0 +1,200 @@
+use crate::shared::mod_sub;
+use crate::shared::number_theory::sieve;
+use crate::shared::rsa::PrivateKey;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::One;
+
+// Pollard's rho only takes a gcd every this many steps.
+const RHO_BATCH_SIZE: u64 = 64;
+
+// The nontrivial factorization n = p * q with p <= q, if d is a proper divisor.
+fn split(n: &BigUint, d: &BigUint) -> Option<(BigUint, BigUint)> {
+    if d.is_one() || d >= n {
+        return None;
+    }
+
+    let (p, q) = (d.clone(), n / d);
+    Some(if p <= q { (p, q) } else { (q, p) })
+}
+
+// Rebuilds the private key for a public key from its factors, which then works with rsa::decrypt through keypair.
+#[must_use]
+pub fn private_key(
+    public_key: &(BigUint, BigUint),
+    factors: &(BigUint, BigUint),
+) -> Option<PrivateKey> {
+    let (n, e) = public_key;
+    let (p, q) = factors;
+    if &(p * q) != n {
+        return None;
+    }
+
+    PrivateKey::from_primes_and_exponent(p, q, e)
+}
+
+// Fast when p and q are close to sqrt(n): then n = a^2 - b^2 for a small a - ceil(sqrt(n)).
+#[must_use]
+pub fn fermat(n: &BigUint, max_iterations: u64) -> Option<(BigUint, BigUint)> {
+    let mut a = n.sqrt();
+    if &a * &a < *n {
+        a += 1u8;
+    }
+
+    for _ in 0..max_iterations {
+        let b2 = &a * &a - n;
+        let b = b2.sqrt();
+        if &b * &b == b2 {
+            return split(n, &(&a - &b));
+        }
+
+        a += 1u8;
+    }
+    None
+}
+
+// Finds a factor p in roughly sqrt(p) steps, with Floyd's cycle detection on x^2 + c mod n.
+// Differences are multiplied together so a gcd is only needed once per batch, which is replayed if it overshoots.
+#[must_use]
+pub fn pollard_rho(n: &BigUint, max_iterations: u64) -> Option<(BigUint, BigUint)> {
+    if n.is_even() {
+        return split(n, &BigUint::from(2u8));
+    }
+
+    for c in 1u8..=10 {
+        let f = |x: &BigUint| (x * x + c).mod_floor(n);
+        let (mut x, mut y) = (BigUint::from(2u8), BigUint::from(2u8));
+        let mut iterations = 0;
+        while iterations < max_iterations {
+            let (x_start, y_start) = (x.clone(), y.clone());
+            let mut product = BigUint::one();
+            for _ in 0..RHO_BATCH_SIZE {
+                x = f(&x);
+                y = f(&f(&y));
+                product = (product * mod_sub(&x, &y, n)).mod_floor(n);
+            }
+            iterations += RHO_BATCH_SIZE;
+
+            let g = product.gcd(n);
+            if g.is_one() {
+                continue;
+            }
+
+            if g < *n {
+                return split(n, &g);
+            }
+
+            // The batch contains the collision, so step through it again one gcd at a time.
+            (x, y) = (x_start, y_start);
+            let g = (0..RHO_BATCH_SIZE)
+                .map(|_| {
+                    x = f(&x);
+                    y = f(&f(&y));
+                    mod_sub(&x, &y, n).gcd(n)
+                })
+                .find(|g| !g.is_one())?;
+            if let Some(factors) = split(n, &g) {
+                return Some(factors);
+            }
+
+            // Both factors were found at the same time, so try again with another polynomial.
+            break;
+        }
+    }
+    None
+}
+
+// The largest power of every prime below bound that is still below bound, the exponent used by the p - 1 and p + 1 methods.
+fn prime_powers(bound: u32) -> impl Iterator<Item = u64> {
+    sieve(bound).into_iter().map(move |q| {
+        let mut power = u64::from(q);
+        while power * u64::from(q) <= u64::from(bound) {
+            power *= u64::from(q);
+        }
+        power
+    })
+}
+
+// Works when p - 1 only has prime power factors below bound: then p - 1 divides M and a^M = 1 mod p.
+#[must_use]
+pub fn pollard_p_minus_1(n: &BigUint, bound: u32) -> Option<(BigUint, BigUint)> {
+    let mut a = BigUint::from(2u8);
+    for power in prime_powers(bound) {
+        a = a.modpow(&BigUint::from(power), n);
+        let g = mod_sub(&a, &BigUint::one(), n).gcd(n);
+        if !g.is_one() {
+            return split(n, &g);
+        }
+    }
+    None
+}
+
+// V_m(v) mod n for the Lucas sequence V_0 = 2, V_1 = v, V_(k + 1) = v * V_k - V_(k - 1), with a Montgomery ladder.
+fn lucas_v(v: &BigUint, m: u64, n: &BigUint) -> BigUint {
+    let two = &BigUint::from(2u8);
+    let (mut x, mut y) = (v.clone(), mod_sub(&(v * v), two, n));
+    for i in (0..m.ilog2()).rev() {
+        let xy = mod_sub(&(&x * &y), v, n);
+        if (m >> i) & 1 == 1 {
+            x = xy;
+            y = mod_sub(&(&y * &y), two, n);
+        } else {
+            y = xy;
+            x = mod_sub(&(&x * &x), two, n);
+        }
+    }
+    x
+}
+
+// Williams' p + 1 method works when p + 1 only has prime power factors below bound, but only if v^2 - 4 happens to be
+// a quadratic non-residue mod p. That's unknown, so a few starting values are tried.
+#[must_use]
+pub fn williams_p_plus_1(n: &BigUint, bound: u32) -> Option<(BigUint, BigUint)> {
+    (3u8..=10).find_map(|start| {
+        let mut v = BigUint::from(start);
+        for power in prime_powers(bound) {
+            v = lucas_v(&v, power, n);
+            let g = mod_sub(&v, &BigUint::from(2u8), n).gcd(n);
+            if !g.is_one() {
+                return split(n, &g);
+            }
+        }
+        None
+    })
+}
+
+// Bernstein's batch GCD: the product of all moduli is computed with a product tree, then a remainder tree gives
+// P mod n_i^2 for every modulus, and gcd((P mod n_i^2) / n_i, n_i) is the product of its factors shared with the others.
+// The result has the factors for every modulus that shares exactly one prime with the rest.
+#[must_use]
+pub fn batch_gcd(moduli: &[BigUint]) -> Vec<Option<(BigUint, BigUint)>> {
+    if moduli.is_empty() {
+        return Vec::new();
+    }
+
+    let mut tree = vec![moduli.to_vec()];
+    while tree.last().unwrap().len() > 1 {
+        let level = tree
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| pair.iter().product())
+            .collect();
+        tree.push(level);
+    }
+
+    let mut remainders = tree.pop().unwrap();
+    while let Some(level) = tree.pop() {
+        remainders = level
+            .iter()
+            .enumerate()
+            .map(|(i, n)| &remainders[i / 2] % (n * n))
+            .collect();
+    }
+
+    moduli
+        .iter()
+        .zip(remainders)
+        .map(|(n, remainder)| split(n, &(remainder / n).gcd(n)))
+        .collect()
+}
@@ -0,0 +1,25 @@
+use crate::shared::mod_sub;
+use crate::shared::polynomial::{mod_gcd, padded_polynomial};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+// Two related messages m1 and m2 = a * m1 + b for a known (a, b), encrypted under the same public key with a small e.
+// m1 is a root of both x^e - c1 and (a * x + b)^e - c2 mod n, and their gcd is almost always just x - m1.
+// Euclid's algorithm on polynomials of degree e makes this quadratic in e.
+#[must_use]
+pub fn attack(
+    public_key: &(BigUint, BigUint),
+    c1: &BigUint,
+    c2: &BigUint,
+    padding: &(BigUint, BigUint),
+) -> Option<BigUint> {
+    let (n, e) = public_key;
+    let (a, b) = padding;
+    let e = u32::try_from(e).ok()?;
+    let g1 = padded_polynomial(e, n, c1, &BigUint::one(), &BigUint::zero());
+    let g2 = padded_polynomial(e, n, c2, a, b);
+    match mod_gcd(&g1, &g2, n)?.as_slice() {
+        [root, _] => Some(mod_sub(&BigUint::zero(), root, n)),
+        _ => None,
+    }
+}
@@ -0,0 +1,99 @@
+use crate::shared::lattice::lll;
+use crate::shared::number_theory::nth_root_exact;
+use crate::shared::polynomial::{integer_roots, padded_polynomial};
+use crate::shared::{crt, mod_inv};
+use num_bigint::{BigInt, BigUint, ToBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+// A public key, a ciphertext and the (a, b) of the linear padding a * m + b.
+pub type PaddedCiphertext = ((BigUint, BigUint), BigUint, (BigUint, BigUint));
+
+fn exponent(ciphertexts: &[((BigUint, BigUint), BigUint)]) -> Option<u32> {
+    let ((_, e), _) = ciphertexts.first()?;
+    if ciphertexts.iter().any(|((_, e_), _)| e_ != e) {
+        return None;
+    }
+
+    u32::try_from(e).ok()
+}
+
+// The same message encrypted under different public keys with the same small e, as (public key, ciphertext) pairs.
+// Works as soon as m^e is smaller than the product of the moduli, so usually e ciphertexts are enough.
+#[must_use]
+pub fn attack(ciphertexts: &[((BigUint, BigUint), BigUint)]) -> Option<BigUint> {
+    let e = exponent(ciphertexts)?;
+    let congruences = ciphertexts
+        .iter()
+        .map(|((n, _), c)| (c.clone(), n.clone()))
+        .collect::<Vec<(BigUint, BigUint)>>();
+    let (c, _) = crt(&congruences)?;
+    nth_root_exact(&c, e)
+}
+
+// Like attack, but every ciphertext encrypts a * m + b for its own known (a, b), and m must be smaller than bound.
+// The per-key polynomials are combined with the CRT into one monic polynomial modulo the product of the moduli N,
+// and Håstad's lattice finds a multiple of it with small coefficients that has m as a root over the integers.
+// This needs roughly bound^(e(e + 1) / 2) < N.
+#[must_use]
+pub fn attack_linear_padding(ciphertexts: &[PaddedCiphertext], bound: &BigUint) -> Option<BigUint> {
+    let keys = ciphertexts
+        .iter()
+        .map(|(public_key, c, _)| (public_key.clone(), c.clone()))
+        .collect::<Vec<((BigUint, BigUint), BigUint)>>();
+    let e = exponent(&keys)?;
+    let degree = usize::try_from(e).unwrap();
+
+    // Make every polynomial monic mod its own n, then glue the coefficients together.
+    let mut coefficients = vec![Vec::new(); degree + 1];
+    for ((n, _), c, (a, b)) in ciphertexts {
+        let a_inv = mod_inv(&a.modpow(&BigUint::from(e), n), n)?;
+        padded_polynomial(e, n, c, a, b)
+            .into_iter()
+            .enumerate()
+            .for_each(|(j, coefficient)| {
+                coefficients[j].push(((coefficient * &a_inv).mod_floor(n), n.clone()));
+            });
+    }
+    let mut g = Vec::new();
+    let mut modulus = BigUint::one();
+    for congruences in coefficients {
+        let (coefficient, n) = crt(&congruences)?;
+        g.push(coefficient.to_bigint().unwrap());
+        modulus = n;
+    }
+    let n = &modulus.to_bigint().unwrap();
+
+    // Rows N * x^j for j < e and g(x), evaluated at bound * x so short rows are polynomials that stay small on [0, bound].
+    let x = &bound.to_bigint().unwrap();
+    let mut basis = (0..degree)
+        .map(|j| {
+            let mut row = vec![BigInt::zero(); degree + 1];
+            row[j] = n * x.pow(u32::try_from(j).unwrap());
+            row
+        })
+        .collect::<Vec<Vec<BigInt>>>();
+    basis.push(
+        g.iter()
+            .enumerate()
+            .map(|(j, coefficient)| coefficient * x.pow(u32::try_from(j).unwrap()))
+            .collect(),
+    );
+
+    let is_solution = |m: &BigUint| {
+        ciphertexts
+            .iter()
+            .all(|((n, e), c, (a, b))| (a * m + b).modpow(e, n) == c.mod_floor(n))
+    };
+    lll(&basis)?.iter().find_map(|row| {
+        let h = row
+            .iter()
+            .enumerate()
+            .map(|(j, coefficient)| coefficient / x.pow(u32::try_from(j).unwrap()))
+            .collect::<Vec<BigInt>>();
+        integer_roots(&h, &BigInt::zero(), x)
+            .into_iter()
+            .filter_map(|m| m.to_biguint())
+            .find(|m| is_solution(m))
+    })
+}
@@ -1,12 +1,292 @@
-use std::collections::HashMap;
-
-#[must_use]
-pub fn parse_key_value(input: &str) -> HashMap<String, String> {
-    input
-        .split('&')
-        .map(|s| {
-            let index = s.chars().position(|c| c == '=').unwrap();
-            (s[0..index].to_owned(), s[index + 1..].to_owned())
-        })
-        .collect()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escaping {
+    None,
+    Percent,
+    Quote,
+}
+
+// Offsets are byte offsets into the input being decoded, or into the key or value being encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    MissingSeparator(usize),
+    Metacharacter(usize),
+    InvalidEscape(usize),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyValues {
+    pairs: Vec<(String, String)>,
+}
+
+impl KeyValues {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_owned(), value.to_owned()));
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[must_use]
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for KeyValues {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+pub struct Codec {
+    pair_separator: char,
+    key_value_separator: char,
+    escaping: Escaping,
+    strict: bool,
+}
+
+impl Codec {
+    #[must_use]
+    pub fn new(
+        pair_separator: char,
+        key_value_separator: char,
+        escaping: Escaping,
+        strict: bool,
+    ) -> Self {
+        assert_ne!(pair_separator, key_value_separator);
+        Self {
+            pair_separator,
+            key_value_separator,
+            escaping,
+            strict,
+        }
+    }
+
+    #[must_use]
+    pub fn new_query() -> Self {
+        Self::new('&', '=', Escaping::Percent, false)
+    }
+
+    #[must_use]
+    pub fn new_cookie() -> Self {
+        Self::new(';', '=', Escaping::Percent, false)
+    }
+
+    fn is_metacharacter(&self, c: char) -> bool {
+        c == self.pair_separator
+            || c == self.key_value_separator
+            || match self.escaping {
+                Escaping::None => false,
+                Escaping::Percent => c == '%',
+                Escaping::Quote => c == '"' || c == '\\',
+            }
+    }
+
+    pub fn escape(&self, component: &str) -> Result<String, Error> {
+        if let Some((i, _)) = component
+            .char_indices()
+            .find(|&(_, c)| self.is_metacharacter(c))
+        {
+            if self.strict || self.escaping == Escaping::None {
+                return Err(Error::Metacharacter(i));
+            }
+        }
+
+        match self.escaping {
+            Escaping::None => Ok(component.to_owned()),
+            Escaping::Percent => {
+                let mut escaped = String::with_capacity(component.len());
+                component.chars().for_each(|c| {
+                    // Spaces are not metacharacters, but they are conventionally escaped anyway.
+                    if c == ' ' || self.is_metacharacter(c) {
+                        let mut buf = [0; 4];
+                        c.encode_utf8(&mut buf)
+                            .bytes()
+                            .for_each(|b| escaped.push_str(&format!("%{b:02X}")));
+                    } else {
+                        escaped.push(c);
+                    }
+                });
+                Ok(escaped)
+            }
+            Escaping::Quote => {
+                if !component.chars().any(|c| self.is_metacharacter(c)) {
+                    return Ok(component.to_owned());
+                }
+
+                let mut escaped = String::with_capacity(component.len() + 2);
+                escaped.push('"');
+                component.chars().for_each(|c| {
+                    if c == '"' || c == '\\' {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                });
+                escaped.push('"');
+                Ok(escaped)
+            }
+        }
+    }
+
+    pub fn encode(&self, key_values: &KeyValues) -> Result<String, Error> {
+        let mut encoded = String::new();
+        for (i, (key, value)) in key_values.iter().enumerate() {
+            if i > 0 {
+                encoded.push(self.pair_separator);
+            }
+            encoded.push_str(&self.escape(key)?);
+            encoded.push(self.key_value_separator);
+            encoded.push_str(&self.escape(value)?);
+        }
+        Ok(encoded)
+    }
+
+    // Decodes a single key or value starting at start, returning it and the offset of the character that ended it.
+    fn decode_component(
+        &self,
+        input: &str,
+        start: usize,
+        is_key: bool,
+    ) -> Result<(String, usize), Error> {
+        let mut bytes = Vec::new();
+        let mut iter = input[start..]
+            .char_indices()
+            .map(|(i, c)| (start + i, c))
+            .peekable();
+        let mut end = input.len();
+        if self.escaping == Escaping::Quote && input[start..].starts_with('"') {
+            iter.next();
+            let mut closed = false;
+            while let Some((i, c)) = iter.next() {
+                match c {
+                    '"' => {
+                        closed = true;
+                        break;
+                    }
+                    '\\' => match iter.next() {
+                        Some((_, c)) => push_char(&mut bytes, c),
+                        None => return Err(Error::InvalidEscape(i)),
+                    },
+                    _ => push_char(&mut bytes, c),
+                }
+            }
+            if !closed {
+                return Err(Error::InvalidEscape(start));
+            }
+            if let Some(&(i, c)) = iter.peek() {
+                // Anything between the closing quote and the next separator is suspicious.
+                if self.strict
+                    && c != self.pair_separator
+                    && (!is_key || c != self.key_value_separator)
+                {
+                    return Err(Error::Metacharacter(i));
+                }
+            }
+        }
+
+        while let Some((i, c)) = iter.next() {
+            if c == self.pair_separator || (is_key && c == self.key_value_separator) {
+                end = i;
+                break;
+            }
+
+            if self.escaping == Escaping::Percent && c == '%' {
+                let escape = input.get(i + 1..i + 3).and_then(|hex| {
+                    if hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        u8::from_str_radix(hex, 16).ok()
+                    } else {
+                        None
+                    }
+                });
+                match escape {
+                    Some(b) => {
+                        bytes.push(b);
+                        iter.next();
+                        iter.next();
+                    }
+                    None if self.strict => return Err(Error::InvalidEscape(i)),
+                    // Leave malformed escapes as they are.
+                    None => bytes.push(b'%'),
+                }
+                continue;
+            }
+
+            if self.strict && self.is_metacharacter(c) {
+                return Err(Error::Metacharacter(i));
+            }
+
+            push_char(&mut bytes, c);
+        }
+
+        let component = if self.strict {
+            String::from_utf8(bytes).map_err(|_| Error::InvalidEscape(start))?
+        } else {
+            String::from_utf8_lossy(&bytes).into_owned()
+        };
+        Ok((component, end))
+    }
+
+    pub fn decode(&self, input: &str) -> Result<KeyValues, Error> {
+        let mut key_values = KeyValues::new();
+        let mut start = 0;
+        while start < input.len() {
+            let (key, end) = self.decode_component(input, start, true)?;
+            if end == input.len() || input[end..].starts_with(self.pair_separator) {
+                if self.strict {
+                    return Err(Error::MissingSeparator(start));
+                }
+
+                // Skip empty pairs and treat a lone key as having an empty value.
+                if end > start {
+                    key_values.pairs.push((key, String::new()));
+                }
+                start = end + self.pair_separator.len_utf8();
+                continue;
+            }
+
+            let (value, end) =
+                self.decode_component(input, end + self.key_value_separator.len_utf8(), false)?;
+            key_values.pairs.push((key, value));
+            start = end + self.pair_separator.len_utf8();
+        }
+        Ok(key_values)
+    }
+}
+
+fn push_char(bytes: &mut Vec<u8>, c: char) {
+    let mut buf = [0; 4];
+    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
 }
@@ -0,0 +1,85 @@
+use crate::shared::xor::{frequency_analysis_ranked, Scorer};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keystream {
+    pub bytes: Vec<u8>,
+    // Between 0 and 1, where 1 means the byte was derived from known plaintext.
+    pub confidence: Vec<f64>,
+}
+
+impl Keystream {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn set(&mut self, i: usize, byte: u8, confidence: f64) {
+        if i >= self.bytes.len() {
+            self.bytes.resize(i + 1, 0);
+            self.confidence.resize(i + 1, 0.0);
+        }
+        self.bytes[i] = byte;
+        self.confidence[i] = confidence;
+    }
+
+    // Fixes the keystream bytes under pt, assuming pt is the plaintext of ct at offset.
+    pub fn apply_crib(&mut self, ct: &[u8], offset: usize, pt: &[u8]) {
+        assert!(offset + pt.len() <= ct.len());
+        pt.iter()
+            .enumerate()
+            .for_each(|(i, b)| self.set(offset + i, ct[offset + i] ^ b, 1.0));
+    }
+
+    // Only decrypts the part of ct that is covered by the keystream.
+    #[must_use]
+    pub fn decrypt(&self, ct: &[u8]) -> Vec<u8> {
+        ct.iter().zip(&self.bytes).map(|(c, k)| c ^ k).collect()
+    }
+}
+
+// The probability of the best key byte, treating the (log10) scores as log-likelihoods.
+fn confidence(scores: &[f64]) -> f64 {
+    match scores.first() {
+        Some(best) => 1.0 / scores.iter().map(|s| 10f64.powf(s - best)).sum::<f64>(),
+        None => 0.0,
+    }
+}
+
+// Recovers the keystream shared by all ciphertexts column by column, using every ciphertext that is long enough.
+// Cribs are (ciphertext index, offset, known plaintext) triples and take precedence over frequency analysis.
+#[must_use]
+pub fn recover<T: AsRef<[u8]>, S: Scorer + ?Sized>(
+    cts: &[T],
+    cribs: &[(usize, usize, &[u8])],
+    scorer: &S,
+) -> Keystream {
+    let max_length = cts.iter().map(|ct| ct.as_ref().len()).max().unwrap_or(0);
+    let mut keystream = Keystream::new();
+    (0..max_length).for_each(|i| {
+        let column = cts
+            .iter()
+            .filter_map(|ct| ct.as_ref().get(i).copied())
+            .collect::<Vec<u8>>();
+        let candidates = frequency_analysis_ranked(&column, scorer, 256);
+        let scores = candidates
+            .iter()
+            .map(|(score, _, _)| *score)
+            .collect::<Vec<f64>>();
+        let byte = candidates.first().map_or(0, |(_, k, _)| *k);
+        keystream.set(i, byte, confidence(&scores));
+    });
+    cribs
+        .iter()
+        .for_each(|&(j, offset, pt)| keystream.apply_crib(cts[j].as_ref(), offset, pt));
+    keystream
+}
@@ -0,0 +1,106 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{Signed, Zero};
+
+// The Lovász condition uses delta = 3/4.
+const DELTA: (u32, u32) = (3, 4);
+
+fn dot(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// Exact LLL reduction of linearly independent rows, using Cohen's integral version of the algorithm.
+// The Gram-Schmidt coefficients are kept as fractions mu[k][j] = lambda[k][j] / d[j + 1] with integer numerators,
+// where d[j + 1] is the Gram determinant of the first j + 1 rows, so nothing is ever rounded.
+// Returns None if the rows are linearly dependent.
+#[must_use]
+pub fn lll(basis: &[Vec<BigInt>]) -> Option<Vec<Vec<BigInt>>> {
+    let n = basis.len();
+    let mut b = basis.to_vec();
+    if b.iter().any(|row| row.iter().all(Zero::is_zero)) {
+        return None;
+    }
+    if n < 2 {
+        return Some(b);
+    }
+
+    let mut d = vec![BigInt::zero(); n + 1];
+    let mut lambda = vec![vec![BigInt::zero(); n]; n];
+    d[0] = BigInt::from(1);
+    d[1] = dot(&b[0], &b[0]);
+    let mut k = 1;
+    let mut k_max = 0;
+    while k < n {
+        if k > k_max {
+            k_max = k;
+            for j in 0..=k {
+                let mut u = dot(&b[k], &b[j]);
+                for i in 0..j {
+                    u = (&d[i + 1] * u - &lambda[k][i] * &lambda[j][i]) / &d[i];
+                }
+                if j < k {
+                    lambda[k][j] = u;
+                } else {
+                    if u.is_zero() {
+                        return None;
+                    }
+                    d[k + 1] = u;
+                }
+            }
+        }
+
+        reduce(&mut b, &mut lambda, &d, k, k - 1);
+        let (numerator, denominator) = DELTA;
+        if BigInt::from(denominator)
+            * (&d[k + 1] * &d[k - 1] + &lambda[k][k - 1] * &lambda[k][k - 1])
+            < BigInt::from(numerator) * &d[k] * &d[k]
+        {
+            swap(&mut b, &mut lambda, &mut d, k, k_max);
+            k = k.max(2) - 1;
+        } else {
+            (0..k - 1)
+                .rev()
+                .for_each(|l| reduce(&mut b, &mut lambda, &d, k, l));
+            k += 1;
+        }
+    }
+    Some(b)
+}
+
+// Size reduces row k with row l.
+fn reduce(b: &mut [Vec<BigInt>], lambda: &mut [Vec<BigInt>], d: &[BigInt], k: usize, l: usize) {
+    if BigInt::from(2) * lambda[k][l].abs() <= d[l + 1] {
+        return;
+    }
+
+    // The integer nearest to lambda[k][l] / d[l + 1].
+    let q = (BigInt::from(2) * &lambda[k][l] + &d[l + 1]).div_floor(&(BigInt::from(2) * &d[l + 1]));
+    let row = b[l].iter().map(|x| &q * x).collect::<Vec<BigInt>>();
+    b[k].iter_mut().zip(row).for_each(|(x, y)| *x -= y);
+    lambda[k][l] -= &q * &d[l + 1];
+    let (rows, row_k) = lambda.split_at_mut(k);
+    row_k[0][..l]
+        .iter_mut()
+        .zip(&rows[l][..l])
+        .for_each(|(x, y)| *x -= &q * y);
+}
+
+fn swap(
+    b: &mut [Vec<BigInt>],
+    lambda: &mut [Vec<BigInt>],
+    d: &mut [BigInt],
+    k: usize,
+    k_max: usize,
+) {
+    b.swap(k, k - 1);
+    let (rows, row_k) = lambda.split_at_mut(k);
+    rows[k - 1][..k - 1].swap_with_slice(&mut row_k[0][..k - 1]);
+    let l = lambda[k][k - 1].clone();
+    let new_d = (&d[k - 1] * &d[k + 1] + &l * &l) / &d[k];
+    lambda[k + 1..=k_max].iter_mut().for_each(|row| {
+        let t = row[k].clone();
+        row[k] = (&d[k + 1] * &row[k - 1] - &l * &t) / &d[k];
+        row[k - 1] = (&new_d * t + &l * &row[k]) / &d[k + 1];
+    });
+    d[k] = new_d;
+}
@@ -0,0 +1,58 @@
+use crate::shared::mod_inv;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+
+// The oracle returns whether the plaintext of a ciphertext is even.
+#[must_use]
+pub fn attack_parity<O: FnMut(&BigUint) -> bool>(
+    public_key: &(BigUint, BigUint),
+    c: &BigUint,
+    mut oracle: O,
+) -> BigUint {
+    attack(public_key, c, 1, |c| BigUint::from(u8::from(!oracle(c))))
+}
+
+// The oracle returns (at least) the lowest bits bits of the plaintext of a ciphertext.
+#[must_use]
+pub fn attack<O: FnMut(&BigUint) -> BigUint>(
+    public_key: &(BigUint, BigUint),
+    c: &BigUint,
+    bits: u32,
+    oracle: O,
+) -> BigUint {
+    attack_with_progress(public_key, c, bits, oracle, |_, _| {})
+}
+
+// Progress gets the integer bounds on the plaintext after every oracle query.
+#[must_use]
+pub fn attack_with_progress<O: FnMut(&BigUint) -> BigUint, P: FnMut(&BigUint, &BigUint)>(
+    public_key: &(BigUint, BigUint),
+    c: &BigUint,
+    bits: u32,
+    mut oracle: O,
+    mut progress: P,
+) -> BigUint {
+    let (n, e) = public_key;
+    assert!(n.is_odd() && bits > 0);
+    let modulus = &(BigUint::one() << bits);
+    let n_inv = &mod_inv(&n.mod_floor(modulus), modulus).unwrap();
+    let multiplier = &modulus.modpow(e, n);
+    let mut c = c.clone();
+    // After i queries m lies in [k * n / 2^(bits * i), (k + 1) * n / 2^(bits * i)), and the bounds are never rounded.
+    let mut k = BigUint::zero();
+    let mut denominator = BigUint::one();
+    while denominator <= *n {
+        // The plaintext is now x = 2^(bits * i) * m mod n, and 2^bits * x mod n = 2^bits * x - j * n for j < 2^bits.
+        // The lowest bits of that are -j * n mod 2^bits, which reveals j.
+        c = (c * multiplier).mod_floor(n);
+        let lowest_bits = oracle(&c).mod_floor(modulus);
+        let j = ((modulus - lowest_bits) * n_inv).mod_floor(modulus);
+        k = (k << bits) + j;
+        denominator <<= bits;
+        let lower = (&k * n).div_ceil(&denominator);
+        let upper = ((&k + 1u8) * n - 1u8) / &denominator;
+        progress(&lower, &upper);
+    }
+    (k * n).div_ceil(&denominator)
+}
@@ -1,3 +1,7 @@
+use rand::{Error, RngCore, SeedableRng};
+use std::ops::RangeInclusive;
+
+#[derive(Clone)]
 pub struct MersenneTwister {
     w: u32,
     n: usize,
@@ -12,6 +16,10 @@ pub struct MersenneTwister {
     l: u32,
     mt: Vec<u32>,
     index: usize,
+    // How many outputs previous can step back over, which is unbounded for cloned states.
+    outputs: usize,
+    // Whether the lower bits of the first word were lost by untwisting.
+    untwisted: bool,
     lower_mask: u32,
     upper_mask: u32,
 }
@@ -48,6 +56,8 @@ impl MersenneTwister {
             l,
             mt: vec![0; n],
             index: n + 1,
+            outputs: 0,
+            untwisted: false,
             lower_mask,
             upper_mask,
         }
@@ -72,6 +82,8 @@ impl MersenneTwister {
 
     pub fn seed(&mut self, f: u32, seed: u32) {
         self.index = self.n;
+        self.outputs = 0;
+        self.untwisted = false;
         self.mt[0] = seed;
         (1..self.n).for_each(|i| {
             self.mt[i] = f
@@ -80,6 +92,40 @@ impl MersenneTwister {
         });
     }
 
+    // The reference init_by_array seeding, which is what most language runtimes use.
+    pub fn init_by_array(&mut self, key: &[u32]) {
+        assert!(!key.is_empty());
+        self.seed(1812433253, 19650218);
+        let mut i = 1;
+        let mut j = 0;
+        (0..self.n.max(key.len())).for_each(|_| {
+            self.mt[i] = (self.mt[i]
+                ^ (self.mt[i - 1] ^ (self.mt[i - 1] >> 30)).wrapping_mul(1664525))
+            .wrapping_add(key[j])
+            .wrapping_add(u32::try_from(j).unwrap());
+            i += 1;
+            j += 1;
+            if i >= self.n {
+                self.mt[0] = self.mt[self.n - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        });
+        (0..self.n - 1).for_each(|_| {
+            self.mt[i] = (self.mt[i]
+                ^ (self.mt[i - 1] ^ (self.mt[i - 1] >> 30)).wrapping_mul(1566083941))
+            .wrapping_sub(u32::try_from(i).unwrap());
+            i += 1;
+            if i >= self.n {
+                self.mt[0] = self.mt[self.n - 1];
+                i = 1;
+            }
+        });
+        self.mt[0] = 0x8000_0000;
+    }
+
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<u32> {
         if self.index >= self.n {
@@ -89,6 +135,241 @@ impl MersenneTwister {
             self.twist();
         }
 
+        let y = self.temper(self.mt[self.index]);
+        self.index += 1;
+        self.outputs = self.outputs.saturating_add(1);
+        Some(y)
+    }
+
+    // Steps back over the last output and returns it, so next will return it again.
+    // Returns None if nothing was output yet, as the value before the first output was never generated,
+    // or if the output was the first of an untwisted block, as it can't be recovered.
+    pub fn previous(&mut self) -> Option<u32> {
+        if self.index > self.n || self.outputs == 0 {
+            return None;
+        }
+        if self.index == 1 && self.untwisted {
+            return None;
+        }
+
+        if self.index == 0 {
+            self.untwist();
+        }
+        self.index -= 1;
+        if self.outputs != usize::MAX {
+            self.outputs -= 1;
+        }
+        Some(self.temper(self.mt[self.index]))
+    }
+
+    fn temper(&self, mut y: u32) -> u32 {
+        y ^= (y >> self.u) & self.d;
+        y ^= (y << self.s) & self.b;
+        y ^= (y << self.t) & self.c;
+        y ^= y >> self.l;
+        y
+    }
+
+    fn twist(&mut self) {
+        (0..self.n).for_each(|i| {
+            let x = (self.mt[i] & self.upper_mask) + (self.mt[(i + 1) % self.n] & self.lower_mask);
+            let mut xa = x >> 1;
+            if x % 2 != 0 {
+                xa ^= self.a;
+            }
+            self.mt[i] = self.mt[(i + self.m) % self.n] ^ xa;
+        });
+        self.index = 0;
+        self.untwisted = false;
+    }
+
+    // Restores the state before the last twist, except for the lower bits of the first word which the twist discards.
+    // This means the first output of every earlier block of n outputs can't be recovered.
+    pub fn untwist(&mut self) {
+        // We can only tell whether a was XORed in if it sets the top bit.
+        assert_eq!(self.a >> (self.w - 1), 1);
+        let x = |mt: &[u32], i: usize| {
+            let xa = mt[i] ^ mt[(i + self.m) % self.n];
+            if xa >> (self.w - 1) == 1 {
+                ((xa ^ self.a) << 1) | 1
+            } else {
+                xa << 1
+            }
+        };
+        (0..self.n).rev().for_each(|i| {
+            let upper = x(&self.mt, i) & self.upper_mask;
+            let lower = x(&self.mt, (i + self.n - 1) % self.n) & self.lower_mask;
+            self.mt[i] = upper | lower;
+        });
+        self.index = self.n;
+        self.untwisted = true;
+    }
+
+    // Recovers the seed if the current state is the one it produced, for example after cloning and untwisting.
+    #[must_use]
+    pub fn recover_seed(&self, f: u32) -> Option<u32> {
+        // Newton's iteration doubles the number of correct bits of the inverse of f modulo 2^32 every step.
+        let mut f_inv = f;
+        (0..5).for_each(|_| f_inv = f_inv.wrapping_mul(2u32.wrapping_sub(f.wrapping_mul(f_inv))));
+        // The first word might not have survived untwisting, so invert the recurrence for the second one.
+        let y = self.mt[1].wrapping_sub(1).wrapping_mul(f_inv);
+        let seed = reverse_right(y, self.w - 2, 0xFFFF_FFFF);
+        let mut mt = self.clone();
+        mt.seed(f, seed);
+        (mt.mt[1..] == self.mt[1..]).then_some(seed)
+    }
+}
+
+// Returns every seed in the window for which the generator produces the known (output index, output) pairs.
+#[must_use]
+pub fn brute_force_seeds(
+    template: &MersenneTwister,
+    f: u32,
+    seeds: RangeInclusive<u32>,
+    outputs: &[(usize, u32)],
+) -> Vec<u32> {
+    let mut outputs = outputs.to_owned();
+    outputs.sort_unstable();
+    outputs.dedup();
+    let mut mt = template.clone();
+    seeds
+        .filter(|&seed| {
+            mt.seed(f, seed);
+            let mut index = 0;
+            outputs.iter().all(|&(i, y)| {
+                while index < i {
+                    mt.next();
+                    index += 1;
+                }
+                index += 1;
+                mt.next() == Some(y)
+            })
+        })
+        .collect()
+}
+
+pub struct MersenneTwister64 {
+    n: usize,
+    m: usize,
+    a: u64,
+    b: u64,
+    c: u64,
+    s: u32,
+    t: u32,
+    u: u32,
+    d: u64,
+    l: u32,
+    mt: Vec<u64>,
+    index: usize,
+    lower_mask: u64,
+    upper_mask: u64,
+}
+
+impl MersenneTwister64 {
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        n: usize,
+        m: usize,
+        r: u32,
+        a: u64,
+        b: u64,
+        c: u64,
+        s: u32,
+        t: u32,
+        u: u32,
+        d: u64,
+        l: u32,
+    ) -> Self {
+        let lower_mask = (1 << r) - 1;
+        let upper_mask = !lower_mask;
+        Self {
+            n,
+            m,
+            a,
+            b,
+            c,
+            s,
+            t,
+            u,
+            d,
+            l,
+            mt: vec![0; n],
+            index: n + 1,
+            lower_mask,
+            upper_mask,
+        }
+    }
+
+    #[must_use]
+    pub fn new_mt19937_64() -> Self {
+        Self::new(
+            312,
+            156,
+            31,
+            0xB502_6F5A_A966_19E9,
+            0x71D6_7FFF_EDA6_0000,
+            0xFFF7_EEE0_0000_0000,
+            17,
+            37,
+            29,
+            0x5555_5555_5555_5555,
+            43,
+        )
+    }
+
+    pub fn seed(&mut self, f: u64, seed: u64) {
+        self.index = self.n;
+        self.mt[0] = seed;
+        (1..self.n).for_each(|i| {
+            self.mt[i] = f
+                .wrapping_mul(self.mt[i - 1] ^ (self.mt[i - 1] >> 62))
+                .wrapping_add(u64::try_from(i).unwrap());
+        });
+    }
+
+    pub fn init_by_array(&mut self, key: &[u64]) {
+        assert!(!key.is_empty());
+        self.seed(6364136223846793005, 19650218);
+        let mut i = 1;
+        let mut j = 0;
+        (0..self.n.max(key.len())).for_each(|_| {
+            self.mt[i] = (self.mt[i]
+                ^ (self.mt[i - 1] ^ (self.mt[i - 1] >> 62)).wrapping_mul(3935559000370003845))
+            .wrapping_add(key[j])
+            .wrapping_add(u64::try_from(j).unwrap());
+            i += 1;
+            j += 1;
+            if i >= self.n {
+                self.mt[0] = self.mt[self.n - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        });
+        (0..self.n - 1).for_each(|_| {
+            self.mt[i] = (self.mt[i]
+                ^ (self.mt[i - 1] ^ (self.mt[i - 1] >> 62)).wrapping_mul(2862933555777941757))
+            .wrapping_sub(u64::try_from(i).unwrap());
+            i += 1;
+            if i >= self.n {
+                self.mt[0] = self.mt[self.n - 1];
+                i = 1;
+            }
+        });
+        self.mt[0] = 1 << 63;
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<u64> {
+        if self.index >= self.n {
+            if self.index > self.n {
+                return None;
+            }
+            self.twist();
+        }
+
         let mut y = self.mt[self.index];
         y ^= (y >> self.u) & self.d;
         y ^= (y << self.s) & self.b;
@@ -102,7 +383,7 @@ impl MersenneTwister {
         (0..self.n).for_each(|i| {
             let x = (self.mt[i] & self.upper_mask) + (self.mt[(i + 1) % self.n] & self.lower_mask);
             let mut xa = x >> 1;
-            if x % 2 != 0 {
+            if !x.is_multiple_of(2) {
                 xa ^= self.a;
             }
             self.mt[i] = self.mt[(i + self.m) % self.n] ^ xa;
@@ -111,6 +392,295 @@ impl MersenneTwister {
     }
 }
 
+// Panics when used unseeded, since there's no sensible output to return.
+impl RngCore for MersenneTwister {
+    fn next_u32(&mut self) -> u32 {
+        self.next().expect("The generator was never seeded")
+    }
+
+    // Low word first, like rand_core does.
+    fn next_u64(&mut self) -> u64 {
+        let low = u64::from(self.next_u32());
+        let high = u64::from(self.next_u32());
+        (high << 32) | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.chunks_mut(4).for_each(|chunk| {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for MersenneTwister {
+    type Seed = [u8; 4];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut mt = Self::new_mt19937();
+        mt.seed(1812433253, u32::from_le_bytes(seed));
+        mt
+    }
+
+    // Like the reference implementation, and unlike the default which scrambles the state first.
+    // The upper half is folded into the lower half, so states below 2^32 are used as is.
+    fn seed_from_u64(state: u64) -> Self {
+        let seed = u32::try_from((state ^ (state >> 32)) & 0xFFFF_FFFF).unwrap();
+        Self::from_seed(seed.to_le_bytes())
+    }
+}
+
+impl RngCore for MersenneTwister64 {
+    fn next_u32(&mut self) -> u32 {
+        u32::try_from(self.next_u64() & 0xFFFF_FFFF).unwrap()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next().expect("The generator was never seeded")
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.chunks_mut(8).for_each(|chunk| {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for MersenneTwister64 {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut mt = Self::new_mt19937_64();
+        mt.seed(6364136223846793005, u64::from_le_bytes(seed));
+        mt
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self::from_seed(state.to_le_bytes())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecoveryError {
+    // The number of state bits that the known bits don't determine.
+    Underdetermined(usize),
+    Inconsistent,
+}
+
+#[derive(Clone)]
+struct BitVector(Vec<u64>);
+
+impl BitVector {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    fn flip(&mut self, i: usize) {
+        self.0[i / 64] ^= 1 << (i % 64);
+    }
+
+    fn xor(&mut self, other: &Self) {
+        self.0.iter_mut().zip(&other.0).for_each(|(a, b)| *a ^= b);
+    }
+
+    fn first_one(&self) -> Option<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .find(|(_, &word)| word != 0)
+            .map(|(i, word)| i * 64 + usize::try_from(word.trailing_zeros()).unwrap())
+    }
+}
+
+// Every bit of a symbolic word is the set of unknown state bits that it is the XOR of.
+type SymbolicWord = Vec<BitVector>;
+
+fn symbolic_shift_right(y: &SymbolicWord, shift: u32, mask: u32) -> SymbolicWord {
+    let mut y_ = y.clone();
+    (0..32).for_each(|i| {
+        let j = i + usize::try_from(shift).unwrap();
+        if j < 32 && mask >> i & 1 == 1 {
+            y_[i].xor(&y[j]);
+        }
+    });
+    y_
+}
+
+fn symbolic_shift_left(y: &SymbolicWord, shift: u32, mask: u32) -> SymbolicWord {
+    let mut y_ = y.clone();
+    (0..32usize).for_each(|i| {
+        if let Some(j) = i.checked_sub(usize::try_from(shift).unwrap()) {
+            if mask >> i & 1 == 1 {
+                y_[i].xor(&y[j]);
+            }
+        }
+    });
+    y_
+}
+
+impl MersenneTwister {
+    fn symbolic_temper(&self, y: &SymbolicWord) -> SymbolicWord {
+        let y = symbolic_shift_right(y, self.u, self.d);
+        let y = symbolic_shift_left(&y, self.s, self.b);
+        let y = symbolic_shift_left(&y, self.t, self.c);
+        symbolic_shift_right(&y, self.l, 0xFFFF_FFFF)
+    }
+
+    fn symbolic_twist(&self, mt: &mut [SymbolicWord]) {
+        (0..self.n).for_each(|i| {
+            let next = (i + 1) % self.n;
+            let x = (0..32)
+                .map(|k| {
+                    if self.upper_mask >> k & 1 == 1 {
+                        mt[i][k].clone()
+                    } else {
+                        mt[next][k].clone()
+                    }
+                })
+                .collect::<SymbolicWord>();
+            // xa = x >> 1, XORed with a if the lowest bit of x is set.
+            let mut xa = (0..32)
+                .map(|k| {
+                    if k < 31 {
+                        x[k + 1].clone()
+                    } else {
+                        BitVector::new(x[0].0.len() * 64)
+                    }
+                })
+                .collect::<SymbolicWord>();
+            (0..32).for_each(|k| {
+                if self.a >> k & 1 == 1 {
+                    xa[k].xor(&x[0]);
+                }
+            });
+            let source = &mt[(i + self.m) % self.n];
+            (0..32).for_each(|k| xa[k].xor(&source[k]));
+            mt[i] = xa;
+        });
+    }
+}
+
+// Recovers a generator whose outputs line up with the output indices of the known bits, starting at output 0.
+// Known bits are (output index, bit index, value) triples, where bit 0 is the least significant bit.
+// The parameters are taken from the template, so this works for any 32-bit Mersenne Twister.
+pub fn recover_state(
+    template: &MersenneTwister,
+    known: &[(usize, u32, bool)],
+) -> Result<MersenneTwister, RecoveryError> {
+    let n = template.n;
+    // The unknowns are the bits of the state before the first twist. The twist only uses the upper bits of the first
+    // word (its lower bits are overwritten before they're needed), so those are the only ones that matter.
+    let first_word_bits = (0..32)
+        .filter(|k| template.upper_mask >> k & 1 == 1)
+        .collect::<Vec<usize>>();
+    let unknowns = first_word_bits.len() + (n - 1) * 32;
+    let mut unknown = 0;
+    let mut mt = (0..n)
+        .map(|i| {
+            (0..32)
+                .map(|k| {
+                    let mut bits = BitVector::new(unknowns);
+                    if i > 0 || first_word_bits.contains(&k) {
+                        bits.flip(unknown);
+                        unknown += 1;
+                    }
+                    bits
+                })
+                .collect::<SymbolicWord>()
+        })
+        .collect::<Vec<SymbolicWord>>();
+    let state_bits = mt.clone();
+    template.symbolic_twist(&mut mt);
+
+    let mut known = known.to_owned();
+    known.sort_unstable();
+    let mut pivots: Vec<Option<(BitVector, bool)>> = vec![None; unknowns];
+    let mut generation = 0;
+    let mut tempered: Vec<Option<SymbolicWord>> = vec![None; n];
+    for (index, bit, value) in known {
+        while index / n > generation {
+            template.symbolic_twist(&mut mt);
+            tempered.iter_mut().for_each(|t| *t = None);
+            generation += 1;
+        }
+
+        let i = index % n;
+        let y = tempered[i].get_or_insert_with(|| template.symbolic_temper(&mt[i]));
+        let mut row = y[usize::try_from(bit).unwrap()].clone();
+        let mut rhs = value;
+        let mut independent = false;
+        while let Some(col) = row.first_one() {
+            match &pivots[col] {
+                Some((pivot, pivot_rhs)) => {
+                    row.xor(pivot);
+                    rhs ^= pivot_rhs;
+                }
+                None => {
+                    pivots[col] = Some((row, rhs));
+                    independent = true;
+                    break;
+                }
+            }
+        }
+        // The known bit follows from the previous ones, so it had better agree with them.
+        if !independent && rhs {
+            return Err(RecoveryError::Inconsistent);
+        }
+    }
+
+    let free = pivots.iter().filter(|p| p.is_none()).count();
+    if free > 0 {
+        return Err(RecoveryError::Underdetermined(free));
+    }
+
+    // Every pivot row only contains columns after its pivot, so solve from the last column backwards.
+    let mut solution = BitVector::new(unknowns);
+    (0..unknowns).rev().for_each(|col| {
+        let (row, rhs) = pivots[col].as_ref().unwrap();
+        // The solution doesn't have the pivot column set yet, so only the later columns count.
+        let value = row
+            .0
+            .iter()
+            .zip(&solution.0)
+            .map(|(a, b)| (a & b).count_ones())
+            .sum::<u32>()
+            % 2
+            == 1;
+        if value ^ rhs {
+            solution.flip(col);
+        }
+    });
+
+    let mut recovered = template.clone();
+    (0..n).for_each(|i| {
+        recovered.mt[i] = (0..32).fold(0, |word, k| {
+            let bit = state_bits[i][k]
+                .first_one()
+                .is_some_and(|unknown| solution.get(unknown));
+            word | u32::from(bit) << k
+        });
+    });
+    recovered.index = n;
+    recovered.outputs = 0;
+    recovered.untwisted = false;
+    Ok(recovered)
+}
+
 fn reverse_left(y: u32, shift: u32, mask: u32) -> u32 {
     let w = 32;
     let mut y_ = 0;
@@ -151,6 +721,38 @@ pub fn clone_mt19937(y: &[u32]) -> MersenneTwister {
         mt.mt[mt.index] = yi;
         mt.index += 1;
     }
+    // Whatever produced the outputs might have produced many more before them.
+    mt.outputs = usize::MAX;
+    mt
+}
+
+// Every iteration recovers at least another shift bits, so 64 / shift iterations suffice.
+fn reverse_left_64(y: u64, shift: u32, mask: u64) -> u64 {
+    let mut y_ = y;
+    (0..64 / shift).for_each(|_| y_ = y ^ ((y_ << shift) & mask));
+    y_
+}
+
+fn reverse_right_64(y: u64, shift: u32, mask: u64) -> u64 {
+    let mut y_ = y;
+    (0..64 / shift).for_each(|_| y_ = y ^ ((y_ >> shift) & mask));
+    y_
+}
+
+#[must_use]
+pub fn clone_mt19937_64(y: &[u64]) -> MersenneTwister64 {
+    let mut mt = MersenneTwister64::new_mt19937_64();
+    assert_eq!(y.len(), mt.n);
+    mt.index = 0;
+    while mt.index < mt.n {
+        let mut yi = y[mt.index];
+        yi = reverse_right_64(yi, mt.l, u64::MAX);
+        yi = reverse_left_64(yi, mt.t, mt.c);
+        yi = reverse_left_64(yi, mt.s, mt.b);
+        yi = reverse_right_64(yi, mt.u, mt.d);
+        mt.mt[mt.index] = yi;
+        mt.index += 1;
+    }
     mt
 }
 
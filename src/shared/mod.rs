@@ -3,18 +3,37 @@ use num_integer::Integer;
 use num_traits::{One, Signed, Zero};
 
 pub mod aes;
+pub mod bellcore;
 pub mod bleichenbacher;
+pub mod boneh_durfee;
+pub mod classical;
+pub mod common_modulus;
+pub mod continued_fraction;
 pub mod conversion;
+pub mod coppersmith;
+pub mod crib_drag;
+pub mod der;
 pub mod dh;
 pub mod dsa;
+pub mod factoring;
+pub mod franklin_reiter;
+pub mod hastad;
 pub mod hmac;
 pub mod key_value;
+pub mod keystream;
+pub mod lattice;
+pub mod lsb_oracle;
 pub mod md4;
 pub mod mersenne_twister;
+pub mod number_theory;
 pub mod padding;
+pub mod pem;
+pub mod polynomial;
 pub mod rsa;
 pub mod sha1;
 pub mod sha256;
+pub mod signature_forgery;
+pub mod wiener;
 pub mod xor;
 
 #[must_use]
@@ -63,3 +82,23 @@ pub fn mod_sub(a: &BigUint, b: &BigUint, n: &BigUint) -> BigUint {
         n - (b - a)
     }
 }
+
+// Combines the congruences x = a mod n into a single one modulo the lcm of the moduli, which don't need to be coprime.
+// Returns None if the congruences are inconsistent.
+#[must_use]
+pub fn crt(congruences: &[(BigUint, BigUint)]) -> Option<(BigUint, BigUint)> {
+    congruences
+        .iter()
+        .try_fold((BigUint::zero(), BigUint::one()), |(a1, n1), (a2, n2)| {
+            let g = &n1.gcd(n2);
+            let difference = mod_sub(a2, &a1, n2);
+            if !(&difference % g).is_zero() {
+                return None;
+            }
+
+            let n2_g = &(n2 / g);
+            let k = (difference / g * mod_inv(&(&n1 / g).mod_floor(n2_g), n2_g)?).mod_floor(n2_g);
+            let lcm = &n1 * n2_g;
+            Some(((a1 + n1 * k).mod_floor(&lcm), lcm))
+        })
+}
@@ -0,0 +1,322 @@
+use num_bigint::{BigInt, BigUint, RandBigInt, ToBigInt};
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::RngCore;
+
+// Trial division by these primes quickly gets rid of most candidates.
+const TRIAL_DIVISION_LIMIT: u32 = 1000;
+
+#[must_use]
+pub fn sieve(limit: u32) -> Vec<u32> {
+    let limit = usize::try_from(limit).unwrap();
+    let mut composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    (2..=limit).for_each(|i| {
+        if !composite[i] {
+            primes.push(u32::try_from(i).unwrap());
+            (i * i..=limit).step_by(i).for_each(|j| composite[j] = true);
+        }
+    });
+    primes
+}
+
+// Returns Some if trial division decides whether n is prime.
+fn trial_division(n: &BigUint, primes: &[u32]) -> Option<bool> {
+    for &p in primes {
+        if (n % p).is_zero() {
+            return Some(*n == BigUint::from(p));
+        }
+    }
+    let limit = BigUint::from(*primes.last()?);
+    if n <= &(&limit * &limit) {
+        Some(*n > BigUint::one())
+    } else {
+        None
+    }
+}
+
+// The strong probable prime test to base a, for odd n > 2.
+#[must_use]
+pub fn is_strong_probable_prime(n: &BigUint, a: &BigUint) -> bool {
+    let n_minus_one = &(n - 1u8);
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &(n_minus_one >> s);
+    let mut x = a.modpow(d, n);
+    if x.is_one() || x == *n_minus_one {
+        return true;
+    }
+
+    (1..s).any(|_| {
+        x = x.modpow(&BigUint::from(2u8), n);
+        x == *n_minus_one
+    })
+}
+
+#[must_use]
+pub fn miller_rabin<R: RngCore + ?Sized>(n: &BigUint, rounds: usize, rng: &mut R) -> bool {
+    if let Some(is_prime) = trial_division(n, &[2, 3, 5, 7]) {
+        return is_prime;
+    }
+
+    let two = &BigUint::from(2u8);
+    let n_minus_one = &(n - 1u8);
+    (0..rounds).all(|_| is_strong_probable_prime(n, &rng.gen_biguint_range(two, n_minus_one)))
+}
+
+fn mod_8(n: &BigUint) -> u8 {
+    u8::try_from(n % 8u8).unwrap()
+}
+
+// Only defined for odd n.
+#[must_use]
+pub fn jacobi(a: &BigInt, n: &BigUint) -> i8 {
+    assert!(n.is_odd());
+    let mut a = a.mod_floor(&n.to_bigint().unwrap()).to_biguint().unwrap();
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        let s = a.trailing_zeros().unwrap();
+        a >>= s;
+        let n_mod_8 = mod_8(&n);
+        if s.is_odd() && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        if mod_8(&a) % 4 == 3 && n_mod_8 % 4 == 3 {
+            result = -result;
+        }
+        std::mem::swap(&mut a, &mut n);
+        a = a.mod_floor(&n);
+    }
+
+    if n.is_one() {
+        result
+    } else {
+        0
+    }
+}
+
+fn is_square(n: &BigUint) -> bool {
+    let root = n.sqrt();
+    &root * &root == *n
+}
+
+// The strong Lucas probable prime test with Selfridge's parameters, for odd n > 2.
+#[must_use]
+pub fn is_strong_lucas_probable_prime(n: &BigUint) -> bool {
+    // There's no suitable D for squares.
+    if is_square(n) {
+        return false;
+    }
+
+    let n_ = &n.to_bigint().unwrap();
+    let mut d = BigInt::from(5);
+    loop {
+        match jacobi(&d, n) {
+            -1 => break,
+            0 if d.magnitude() != n => return false,
+            _ => {}
+        }
+        d = if d.sign() == num_bigint::Sign::Plus {
+            -(d + 2u8)
+        } else {
+            -d + 2u8
+        };
+    }
+
+    let p = BigInt::one();
+    let q = &((&p - &d) / 4u8);
+    let half = |x: BigInt| {
+        let x = if x.is_odd() { x + n_ } else { x };
+        (x / 2u8).mod_floor(n_)
+    };
+    let n_plus_one = &(n + 1u8);
+    let s = n_plus_one.trailing_zeros().unwrap();
+    let k = &(n_plus_one >> s);
+    let mut u = BigInt::one();
+    let mut v = p.clone();
+    let mut q_k = q.mod_floor(n_);
+    (0..k.bits() - 1).rev().for_each(|i| {
+        u = (&u * &v).mod_floor(n_);
+        v = (&v * &v - 2u8 * &q_k).mod_floor(n_);
+        q_k = (&q_k * &q_k).mod_floor(n_);
+        if k.bit(i) {
+            let u_ = half(&p * &u + &v);
+            v = half(&d * &u + &p * &v);
+            u = u_;
+            q_k = (&q_k * q).mod_floor(n_);
+        }
+    });
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+
+    (1..s).any(|_| {
+        v = (&v * &v - 2u8 * &q_k).mod_floor(n_);
+        q_k = (&q_k * &q_k).mod_floor(n_);
+        v.is_zero()
+    })
+}
+
+// No composite is known to pass both tests.
+#[must_use]
+pub fn baillie_psw(n: &BigUint) -> bool {
+    if let Some(is_prime) = trial_division(n, &sieve(TRIAL_DIVISION_LIMIT)) {
+        return is_prime;
+    }
+
+    is_strong_probable_prime(n, &BigUint::from(2u8)) && is_strong_lucas_probable_prime(n)
+}
+
+#[must_use]
+pub fn is_prime(n: &BigUint) -> bool {
+    baillie_psw(n)
+}
+
+#[must_use]
+pub fn nth_root_floor(x: &BigUint, n: u32) -> BigUint {
+    x.nth_root(n)
+}
+
+#[must_use]
+pub fn nth_root_exact(x: &BigUint, n: u32) -> Option<BigUint> {
+    let root = x.nth_root(n);
+    if root.pow(n) == *x {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+// A random number of exactly the given bit length with the two top bits set, so the product of two of them doesn't lose a bit.
+fn random_candidate<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    assert!(bits >= 2);
+    let mut candidate = rng.gen_biguint(bits);
+    candidate.set_bit(bits - 1, true);
+    candidate.set_bit(bits - 2, true);
+    candidate.set_bit(0, true);
+    candidate
+}
+
+#[must_use]
+pub fn generate_prime(bits: u64) -> BigUint {
+    generate_prime_with_rng(bits, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_prime_with_rng<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    let primes = &sieve(TRIAL_DIVISION_LIMIT);
+    loop {
+        let candidate = random_candidate(bits, rng);
+        if trial_division(&candidate, primes) != Some(false) && baillie_psw(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+// A prime p such that (p - 1) / 2 is also prime.
+#[must_use]
+pub fn generate_safe_prime(bits: u64) -> BigUint {
+    generate_safe_prime_with_rng(bits, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_safe_prime_with_rng<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    assert!(bits >= 3);
+    let primes = &sieve(TRIAL_DIVISION_LIMIT);
+    let two = &BigUint::from(2u8);
+    loop {
+        let q = random_candidate(bits - 1, rng);
+        // 2q + 1 is divisible by r exactly when q = (r - 1) / 2 mod r.
+        if primes
+            .iter()
+            .skip(1)
+            .any(|&r| q > BigUint::from(r) && &q % r == BigUint::from((r - 1) / 2))
+        {
+            continue;
+        }
+
+        let p = two * &q + 1u8;
+        if trial_division(&q, primes) != Some(false)
+            && is_strong_probable_prime(&p, two)
+            && baillie_psw(&q)
+            && baillie_psw(&p)
+        {
+            return p;
+        }
+    }
+}
+
+// Gordon's algorithm: a prime p where p - 1 has a large prime factor r, p + 1 has a large prime factor s and r - 1 has a large prime factor t.
+#[must_use]
+pub fn generate_strong_prime(bits: u64) -> BigUint {
+    generate_strong_prime_with_rng(bits, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_strong_prime_with_rng<R: RngCore + ?Sized>(bits: u64, rng: &mut R) -> BigUint {
+    assert!(bits >= 32);
+    let factor_bits = bits / 2 - bits / 8;
+    loop {
+        let s = &generate_prime_with_rng(factor_bits, rng);
+        let t = &generate_prime_with_rng(factor_bits, rng);
+        let mut r = 2u8 * t + 1u8;
+        while !baillie_psw(&r) {
+            r += 2u8 * t;
+        }
+        let r = &r;
+
+        // p0 = 1 mod r and p0 = -1 mod s.
+        let rs = &(r * s);
+        let p0 = &(2u8 * s.modpow(&(r - 2u8), r) * s - 1u8);
+        let step = &(2u8 * rs);
+        let low = BigUint::one() << (bits - 1);
+        let high = BigUint::one() << bits;
+        if low <= *p0 || &low + step >= high {
+            continue;
+        }
+
+        // Start at a random j so that p = p0 + j * 2rs has the right bit length.
+        let j_low = (&low - p0).div_ceil(step);
+        let j_high = (&high - p0) / step;
+        if j_low >= j_high {
+            continue;
+        }
+
+        let mut p = p0 + rng.gen_biguint_range(&j_low, &j_high) * step;
+        while p < high {
+            if baillie_psw(&p) {
+                return p;
+            }
+            p += step;
+        }
+    }
+}
+
+// DSA domain parameters: an l bit prime p and an n bit prime q dividing p - 1.
+#[must_use]
+pub fn generate_dsa_primes(l: u64, n: u64) -> (BigUint, BigUint) {
+    generate_dsa_primes_with_rng(l, n, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn generate_dsa_primes_with_rng<R: RngCore + ?Sized>(
+    l: u64,
+    n: u64,
+    rng: &mut R,
+) -> (BigUint, BigUint) {
+    assert!(l > n);
+    let primes = &sieve(TRIAL_DIVISION_LIMIT);
+    loop {
+        let q = generate_prime_with_rng(n, rng);
+        let two_q = &(2u8 * &q);
+        // Like FIPS 186, try a bounded number of p before picking a new q.
+        for _ in 0..4 * l {
+            let mut x = rng.gen_biguint(l);
+            x.set_bit(l - 1, true);
+            let p = &x - (&x % two_q) + 1u8;
+            if p.bits() == l && trial_division(&p, primes) != Some(false) && baillie_psw(&p) {
+                return (p, q);
+            }
+        }
+    }
+}
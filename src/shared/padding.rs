@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 #[must_use]
 pub fn pad_pkcs7(unpadded: &[u8], block_length: usize) -> Vec<u8> {
@@ -31,7 +31,16 @@ pub fn unpad_pkcs7(padded: &[u8], block_length: usize) -> Option<Vec<u8>> {
 
 #[must_use]
 pub fn pad_pkcs1_5(data: &[u8], block_type: u8, k: usize) -> Vec<u8> {
-    let mut rng = rand::thread_rng();
+    pad_pkcs1_5_with_rng(data, block_type, k, &mut rand::thread_rng())
+}
+
+#[must_use]
+pub fn pad_pkcs1_5_with_rng<R: RngCore + ?Sized>(
+    data: &[u8],
+    block_type: u8,
+    k: usize,
+    rng: &mut R,
+) -> Vec<u8> {
     let mut padded = Vec::with_capacity(k);
     padded.push(0x00);
     padded.push(block_type);
@@ -0,0 +1,39 @@
+use crate::shared::conversion::{Alphabet, Base64, DecodeError, Padding};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    MissingBoundary,
+    LabelMismatch,
+    InvalidBase64(DecodeError),
+}
+
+#[must_use]
+pub fn encode(label: &str, der: &[u8]) -> String {
+    let base64 = Base64::new_standard().encode(der);
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    base64.as_bytes().chunks(64).for_each(|line| {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    });
+    pem.push_str(&format!("-----END {label}-----\n"));
+    pem
+}
+
+// Returns the label and the DER contents of the first PEM block in the input.
+pub fn decode(pem: &str) -> Result<(String, Vec<u8>), Error> {
+    let begin = pem.find("-----BEGIN ").ok_or(Error::MissingBoundary)?;
+    let rest = &pem[begin + 11..];
+    let label_end = rest.find("-----").ok_or(Error::MissingBoundary)?;
+    let label = &rest[..label_end];
+    let rest = &rest[label_end + 5..];
+    let end = rest.find("-----END ").ok_or(Error::MissingBoundary)?;
+    if !rest[end + 9..].starts_with(label) || !rest[end + 9 + label.len()..].starts_with("-----") {
+        return Err(Error::LabelMismatch);
+    }
+
+    // Offsets in errors are relative to the start of the encapsulated text.
+    let der = Base64::new(Alphabet::Standard, Padding::Required, None, true)
+        .decode(&rest[..end])
+        .map_err(Error::InvalidBase64)?;
+    Ok((label.to_owned(), der))
+}
@@ -0,0 +1,246 @@
+use crate::shared::{mod_inv, mod_sub};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+
+// Polynomials are vectors of coefficients, lowest degree first.
+
+#[must_use]
+pub fn degree(poly: &[BigInt]) -> Option<usize> {
+    poly.iter().rposition(|c| !c.is_zero())
+}
+
+#[must_use]
+pub fn evaluate(poly: &[BigInt], x: &BigInt) -> BigInt {
+    poly.iter()
+        .rev()
+        .fold(BigInt::zero(), |result, c| result * x + c)
+}
+
+#[must_use]
+pub fn derivative(poly: &[BigInt]) -> Vec<BigInt> {
+    poly.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c * BigInt::from(i))
+        .collect()
+}
+
+// Integers t in [low, high] where poly(t) = 0, or where the sign of poly changes between t and t + 1.
+fn sign_changes(poly: &[BigInt], low: &BigInt, high: &BigInt) -> Vec<BigInt> {
+    if degree(poly).is_none_or(|degree| degree == 0) {
+        return Vec::new();
+    }
+
+    // poly is monotonic between the sign changes of its derivative.
+    let mut boundaries = vec![low.clone()];
+    sign_changes(&derivative(poly), low, high)
+        .into_iter()
+        .for_each(|t| {
+            let t_plus_one = &t + 1u8;
+            boundaries.push(t);
+            boundaries.push(t_plus_one.min(high.clone()));
+        });
+    boundaries.push(high.clone());
+    boundaries.dedup();
+
+    let sign = |x: &BigInt| evaluate(poly, x).signum();
+    let mut changes = Vec::new();
+    boundaries.windows(2).for_each(|window| {
+        let (u, v) = (&window[0], &window[1]);
+        let sign_u = sign(u);
+        if sign_u.is_zero() {
+            changes.push(u.clone());
+            return;
+        }
+
+        if sign(v) == sign_u {
+            return;
+        }
+
+        let (mut left, mut right) = (u.clone(), v.clone());
+        while &right - &left > BigInt::one() {
+            let middle = (&left + &right).div_floor(&BigInt::from(2));
+            if sign(&middle) == sign_u {
+                left = middle;
+            } else {
+                right = middle;
+            }
+        }
+        changes.push(if sign(&right).is_zero() { right } else { left });
+    });
+    if sign(high).is_zero() {
+        changes.push(high.clone());
+    }
+    changes.dedup();
+    changes
+}
+
+// All integer roots of poly in [low, high], in increasing order.
+#[must_use]
+pub fn integer_roots(poly: &[BigInt], low: &BigInt, high: &BigInt) -> Vec<BigInt> {
+    sign_changes(poly, low, high)
+        .into_iter()
+        .filter(|t| evaluate(poly, t).is_zero())
+        .collect()
+}
+
+// The determinant of a square matrix, using Bareiss' fraction-free elimination so every division is exact.
+#[must_use]
+pub fn determinant(matrix: &[Vec<BigInt>]) -> BigInt {
+    let n = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut sign = BigInt::one();
+    let mut previous = BigInt::one();
+    for k in 0..n {
+        let Some(pivot) = (k..n).find(|&i| !a[i][k].is_zero()) else {
+            return BigInt::zero();
+        };
+        if pivot != k {
+            a.swap(pivot, k);
+            sign = -sign;
+        }
+
+        let (rows, rest) = a.split_at_mut(k + 1);
+        let row_k = &rows[k];
+        rest.iter_mut().for_each(|row| {
+            for j in k + 1..n {
+                row[j] = (&row_k[k] * &row[j] - &row[k] * &row_k[j]) / &previous;
+            }
+        });
+        previous = a[k][k].clone();
+    }
+    match n {
+        0 => BigInt::one(),
+        _ => sign * &a[n - 1][n - 1],
+    }
+}
+
+// The determinant of the Sylvester matrix of a and b, taking their lengths minus one as their degrees.
+// Because the degrees are formal, the result is still correct when it's a polynomial in the leading coefficients.
+#[must_use]
+pub fn resultant(a: &[BigInt], b: &[BigInt]) -> BigInt {
+    let (m, n) = (a.len() - 1, b.len() - 1);
+    let mut sylvester = Vec::with_capacity(m + n);
+    (0..n).for_each(|i| {
+        let mut row = vec![BigInt::zero(); m + n];
+        a.iter()
+            .rev()
+            .enumerate()
+            .for_each(|(j, c)| row[i + j] = c.clone());
+        sylvester.push(row);
+    });
+    (0..m).for_each(|i| {
+        let mut row = vec![BigInt::zero(); m + n];
+        b.iter()
+            .rev()
+            .enumerate()
+            .for_each(|(j, c)| row[i + j] = c.clone());
+        sylvester.push(row);
+    });
+    determinant(&sylvester)
+}
+
+// The polynomial with integer coefficients that takes the given values at 0, 1, 2, ...
+// Newton's forward differences give it in the basis x(x - 1)...(x - k + 1) / k!, which is converted back exactly.
+#[must_use]
+pub fn interpolate(values: &[BigInt]) -> Vec<BigInt> {
+    let n = values.len();
+    let mut differences = values.to_vec();
+    let mut leading = Vec::with_capacity(n);
+    while let Some(first) = differences.first() {
+        leading.push(first.clone());
+        differences = differences.windows(2).map(|w| &w[1] - &w[0]).collect();
+    }
+
+    // (n - 1)! times the polynomial, accumulated as sum of leading[k] * (n! / k!) * x(x - 1)...(x - k + 1).
+    let factorial = (1..n).fold(BigInt::one(), |f, i| f * i);
+    let mut poly = vec![BigInt::zero(); n];
+    let mut falling = vec![BigInt::one()];
+    let mut scale = factorial.clone();
+    for (k, delta) in leading.iter().enumerate() {
+        falling
+            .iter()
+            .enumerate()
+            .for_each(|(i, c)| poly[i] += delta * &scale * c);
+        if k + 1 < n {
+            // Multiply falling by (x - k) and divide the scale by k + 1.
+            let mut next = vec![BigInt::zero(); falling.len() + 1];
+            falling.iter().enumerate().for_each(|(i, c)| {
+                next[i + 1] += c;
+                next[i] -= c * k;
+            });
+            falling = next;
+            scale /= k + 1;
+        }
+    }
+    poly.into_iter().map(|c| c / &factorial).collect()
+}
+
+// The coefficients of (a * x + b)^e - c mod n, lowest degree first.
+#[must_use]
+pub fn padded_polynomial(
+    e: u32,
+    n: &BigUint,
+    c: &BigUint,
+    a: &BigUint,
+    b: &BigUint,
+) -> Vec<BigUint> {
+    let mut binomial = BigUint::one();
+    let mut poly = (0..=e)
+        .map(|j| {
+            let coefficient = (&binomial * a.pow(j) * b.pow(e - j)).mod_floor(n);
+            binomial = &binomial * (e - j) / (j + 1);
+            coefficient
+        })
+        .collect::<Vec<BigUint>>();
+    poly[0] = mod_sub(&poly[0], c, n);
+    poly
+}
+
+// Polynomials mod n have BigUint coefficients in [0, n), and the zero polynomial is empty.
+fn trim(mut poly: Vec<BigUint>) -> Vec<BigUint> {
+    while poly.last().is_some_and(Zero::is_zero) {
+        poly.pop();
+    }
+    poly
+}
+
+// Division with remainder mod n. Returns None if the leading coefficient of b isn't invertible, which reveals a factor of n.
+#[must_use]
+pub fn mod_div_rem(
+    a: &[BigUint],
+    b: &[BigUint],
+    n: &BigUint,
+) -> Option<(Vec<BigUint>, Vec<BigUint>)> {
+    let b = trim(b.iter().map(|c| c.mod_floor(n)).collect());
+    let lead_inv = mod_inv(b.last()?, n)?;
+    let mut r = trim(a.iter().map(|c| c.mod_floor(n)).collect());
+    let mut q = vec![BigUint::zero(); (r.len() + 1).saturating_sub(b.len())];
+    while r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let factor = (r.last().unwrap() * &lead_inv).mod_floor(n);
+        b.iter().enumerate().for_each(|(i, c)| {
+            r[shift + i] = mod_sub(&r[shift + i], &(&factor * c), n);
+        });
+        q[shift] = factor;
+        r = trim(r);
+    }
+    Some((q, r))
+}
+
+// The monic gcd of a and b mod n, with Euclid's algorithm. Returns None if both are zero or a non-invertible leading
+// coefficient shows up along the way.
+#[must_use]
+pub fn mod_gcd(a: &[BigUint], b: &[BigUint], n: &BigUint) -> Option<Vec<BigUint>> {
+    let mut a = trim(a.iter().map(|c| c.mod_floor(n)).collect());
+    let mut b = trim(b.iter().map(|c| c.mod_floor(n)).collect());
+    while !b.is_empty() {
+        let (_, r) = mod_div_rem(&a, &b, n)?;
+        a = b;
+        b = r;
+    }
+
+    let lead_inv = mod_inv(a.last()?, n)?;
+    Some(a.iter().map(|c| (c * &lead_inv).mod_floor(n)).collect())
+}
@@ -1,18 +1,239 @@
-use crate::shared::mod_inv;
+use crate::shared::md4::MD4;
+use crate::shared::number_theory::generate_prime_with_rng;
 use crate::shared::padding::{pad_pkcs1_5, unpad_pkcs1_5};
 use crate::shared::sha1::SHA1;
-use num_bigint::BigUint;
-use std::ops::Sub;
+use crate::shared::sha256::SHA256;
+use crate::shared::{mod_inv, mod_sub};
+use num_bigint::{BigUint, RandBigInt};
+use num_integer::Integer;
+use num_traits::One;
+use rand::RngCore;
 
+pub const MD4_ASN1_ID: &[u8; 18] =
+    b"\x30\x20\x30\x0c\x06\x08\x2a\x86\x48\x86\xf7\x0d\x02\x04\x05\x00\x04\x10";
 pub const SHA1_ASN1_ID: &[u8; 15] = b"\x30\x21\x30\x09\x06\x05\x2b\x0e\x03\x02\x1a\x05\x00\x04\x14";
+pub const SHA256_ASN1_ID: &[u8; 19] =
+    b"\x30\x31\x30\x0d\x06\x09\x60\x86\x48\x01\x65\x03\x04\x02\x01\x05\x00\x04\x20";
+pub const DEFAULT_PUBLIC_EXPONENT: u32 = 65537;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hash {
+    Md4,
+    Sha1,
+    Sha256,
+}
+
+impl Hash {
+    #[must_use]
+    pub fn asn1_id(self) -> &'static [u8] {
+        match self {
+            Hash::Md4 => MD4_ASN1_ID,
+            Hash::Sha1 => SHA1_ASN1_ID,
+            Hash::Sha256 => SHA256_ASN1_ID,
+        }
+    }
+
+    // The DER encoded DigestInfo of msg.
+    #[must_use]
+    pub fn digest_info(self, msg: &[u8]) -> Vec<u8> {
+        let mut data = self.asn1_id().to_vec();
+        match self {
+            Hash::Md4 => {
+                let mut hash = [0; 16];
+                MD4::default().hash(msg, &mut hash);
+                data.extend_from_slice(&hash);
+            }
+            Hash::Sha1 => {
+                let mut hash = [0; 20];
+                SHA1::default().hash(msg, &mut hash);
+                data.extend_from_slice(&hash);
+            }
+            Hash::Sha256 => {
+                let mut hash = [0; 32];
+                SHA256::default().hash(msg, &mut hash);
+                data.extend_from_slice(&hash);
+            }
+        }
+        data
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKey {
+    pub n: BigUint,
+    pub e: BigUint,
+    pub d: BigUint,
+    pub p: BigUint,
+    pub q: BigUint,
+    pub dp: BigUint,
+    pub dq: BigUint,
+    pub qinv: BigUint,
+}
+
+impl PrivateKey {
+    // Returns None if q is not invertible mod p, for example when p == q.
+    #[must_use]
+    pub fn from_primes(p: &BigUint, q: &BigUint, e: &BigUint, d: &BigUint) -> Option<Self> {
+        Some(Self {
+            n: p * q,
+            e: e.clone(),
+            d: d.clone(),
+            p: p.clone(),
+            q: q.clone(),
+            dp: d.mod_floor(&(p - 1u8)),
+            dq: d.mod_floor(&(q - 1u8)),
+            qinv: mod_inv(q, p)?,
+        })
+    }
+
+    // Returns None if e is not invertible mod phi(n), or if from_primes does.
+    #[must_use]
+    pub fn from_primes_and_exponent(p: &BigUint, q: &BigUint, e: &BigUint) -> Option<Self> {
+        let phi = (p - 1u8) * (q - 1u8);
+        let d = mod_inv(e, &phi)?;
+        Self::from_primes(p, q, e, &d)
+    }
+
+    #[must_use]
+    pub fn generate(bits: u64) -> Self {
+        Self::generate_with_exponent(bits, &BigUint::from(DEFAULT_PUBLIC_EXPONENT))
+    }
+
+    #[must_use]
+    pub fn generate_with_exponent(bits: u64, e: &BigUint) -> Self {
+        Self::generate_with_rng(bits, e, &mut rand::thread_rng())
+    }
+
+    // Picks new primes until e is invertible, so n has exactly the given bit length.
+    #[must_use]
+    pub fn generate_with_rng<R: RngCore + ?Sized>(bits: u64, e: &BigUint, rng: &mut R) -> Self {
+        assert!(e.is_odd() && *e > BigUint::one());
+        loop {
+            let p = generate_prime_with_rng(bits - bits / 2, rng);
+            let q = generate_prime_with_rng(bits / 2, rng);
+            if p == q {
+                continue;
+            }
+
+            if let Some(private_key) = Self::from_primes_and_exponent(&p, &q, e) {
+                return private_key;
+            }
+        }
+    }
+
+    // A deliberately weak key with a random d of the given bit length, and e derived from it.
+    #[must_use]
+    pub fn generate_with_small_private_exponent(bits: u64, d_bits: u64) -> Self {
+        Self::generate_with_small_private_exponent_with_rng(bits, d_bits, &mut rand::thread_rng())
+    }
+
+    #[must_use]
+    pub fn generate_with_small_private_exponent_with_rng<R: RngCore + ?Sized>(
+        bits: u64,
+        d_bits: u64,
+        rng: &mut R,
+    ) -> Self {
+        assert!(d_bits > 0);
+        loop {
+            let p = generate_prime_with_rng(bits - bits / 2, rng);
+            let q = generate_prime_with_rng(bits / 2, rng);
+            if p == q {
+                continue;
+            }
+
+            let phi = &((&p - 1u8) * (&q - 1u8));
+            for _ in 0..100 {
+                let mut d = rng.gen_biguint(d_bits);
+                d.set_bit(d_bits - 1, true);
+                if let Some(private_key) =
+                    mod_inv(&d, phi).and_then(|e| Self::from_primes(&p, &q, &e, &d))
+                {
+                    return private_key;
+                }
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn public_key(&self) -> (BigUint, BigUint) {
+        (self.n.clone(), self.e.clone())
+    }
+
+    #[must_use]
+    pub fn keypair(&self) -> ((BigUint, BigUint), (BigUint, BigUint)) {
+        (self.public_key(), (self.n.clone(), self.d.clone()))
+    }
+
+    // Garner's recombination of c^dp mod p and c^dq mod q.
+    #[must_use]
+    pub fn decrypt(&self, c: &BigUint) -> BigUint {
+        self.decrypt_with_fault(c, |_, _| {})
+    }
+
+    // The fault can corrupt the halves m1 = c^dp mod p and m2 = c^dq mod q before they're recombined.
+    #[must_use]
+    pub fn decrypt_with_fault<F: FnOnce(&mut BigUint, &mut BigUint)>(
+        &self,
+        c: &BigUint,
+        fault: F,
+    ) -> BigUint {
+        let mut m1 = c.modpow(&self.dp, &self.p);
+        let mut m2 = c.modpow(&self.dq, &self.q);
+        fault(&mut m1, &mut m2);
+        let h = (&self.qinv * mod_sub(&m1, &m2, &self.p)).mod_floor(&self.p);
+        m2 + h * &self.q
+    }
+
+    // Decrypts r^e * c instead, so the timing doesn't depend on c.
+    #[must_use]
+    pub fn decrypt_blinded<R: RngCore + ?Sized>(&self, c: &BigUint, rng: &mut R) -> BigUint {
+        let (r, r_inv) = loop {
+            let r = rng.gen_biguint_range(&BigUint::from(2u8), &self.n);
+            if let Some(r_inv) = mod_inv(&r, &self.n) {
+                break (r, r_inv);
+            }
+        };
+        let c = (r.modpow(&self.e, &self.n) * c).mod_floor(&self.n);
+        (self.decrypt(&c) * r_inv).mod_floor(&self.n)
+    }
+
+    #[must_use]
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        let c = encode_signature(&self.n, msg);
+        self.decrypt(&c).to_bytes_be()
+    }
+
+    // With verify_after_sign, faulty signatures are caught instead of being released.
+    #[must_use]
+    pub fn sign_with_fault<F: FnOnce(&mut BigUint, &mut BigUint)>(
+        &self,
+        msg: &[u8],
+        fault: F,
+        verify_after_sign: bool,
+    ) -> Option<Vec<u8>> {
+        let c = encode_signature(&self.n, msg);
+        let s = self.decrypt_with_fault(&c, fault);
+        if verify_after_sign && s.modpow(&self.e, &self.n) != c {
+            return None;
+        }
+
+        Some(s.to_bytes_be())
+    }
+
+    #[must_use]
+    pub fn sign_blinded<R: RngCore + ?Sized>(&self, msg: &[u8], rng: &mut R) -> Vec<u8> {
+        let c = encode_signature(&self.n, msg);
+        self.decrypt_blinded(&c, rng).to_bytes_be()
+    }
+}
+
+// The challenges use e = 3. Returns None if e is not invertible mod phi(n).
 #[must_use]
-pub fn generate_keypair(p: &BigUint, q: &BigUint) -> ((BigUint, BigUint), (BigUint, BigUint)) {
-    let n = p * q;
-    let phi = p.sub(1u8) * q.sub(1u8);
-    let e = BigUint::from(3u8);
-    let d = mod_inv(&e, &phi).unwrap();
-    ((n.clone(), e), (n, d))
+pub fn generate_keypair(
+    p: &BigUint,
+    q: &BigUint,
+) -> Option<((BigUint, BigUint), (BigUint, BigUint))> {
+    PrivateKey::from_primes_and_exponent(p, q, &BigUint::from(3u8)).map(|key| key.keypair())
 }
 
 #[must_use]
@@ -55,17 +276,18 @@ pub fn decrypt_padded(private_key: &(BigUint, BigUint), ct: &[u8]) -> Option<Vec
     unpad_pkcs1_5(&msg, 0x02, true)
 }
 
+// The PKCS#1 v1.5 encoded SHA-1 DigestInfo of msg.
+#[must_use]
+pub fn encode_signature(n: &BigUint, msg: &[u8]) -> BigUint {
+    let k = usize::try_from(n.bits().div_ceil(8)).unwrap();
+    let data = Hash::Sha1.digest_info(msg);
+    BigUint::from_bytes_be(&pad_pkcs1_5(&data, 0x01, k))
+}
+
 #[must_use]
 pub fn sign(private_key: &(BigUint, BigUint), msg: &[u8]) -> Vec<u8> {
     let (n, _) = private_key;
-    let k = usize::try_from((n.bits() + 7) / 8).unwrap();
-
-    let mut hash = [0; 20];
-    SHA1::default().hash(msg, &mut hash);
-    let mut data = [0; 35];
-    data[0..15].copy_from_slice(SHA1_ASN1_ID);
-    data[15..35].copy_from_slice(&hash);
-    let c = BigUint::from_bytes_be(&pad_pkcs1_5(&data, 0x01, k));
+    let c = encode_signature(n, msg);
     let s = decrypt(private_key, &c);
     s.to_bytes_be()
 }
@@ -75,11 +297,7 @@ pub fn verify(public_key: &(BigUint, BigUint), msg: &[u8], sig: &[u8]) -> bool {
     let (n, _) = public_key;
     let k = usize::try_from((n.bits() + 7) / 8).unwrap();
 
-    let mut hash = [0; 20];
-    SHA1::default().hash(msg, &mut hash);
-    let mut data = [0; 35];
-    data[0..15].copy_from_slice(SHA1_ASN1_ID);
-    data[15..35].copy_from_slice(&hash);
+    let data = Hash::Sha1.digest_info(msg);
     let s = BigUint::from_bytes_be(sig);
     let c = encrypt(public_key, &s);
     let mut bytes = c.to_bytes_be();
@@ -93,3 +311,27 @@ pub fn verify(public_key: &(BigUint, BigUint), msg: &[u8], sig: &[u8]) -> bool {
         .filter(|d| d == &data)
         .is_some()
 }
+
+// Like the verifier from challenge 42: it checks the padding and the DigestInfo, but not that nothing follows it.
+#[must_use]
+pub fn verify_ignoring_trailing_bytes(
+    public_key: &(BigUint, BigUint),
+    msg: &[u8],
+    sig: &[u8],
+    hash: Hash,
+) -> bool {
+    let (n, _) = public_key;
+    let k = usize::try_from(n.bits().div_ceil(8)).unwrap();
+
+    let s = BigUint::from_bytes_be(sig);
+    let c = encrypt(public_key, &s);
+    let mut bytes = c.to_bytes_be();
+    if bytes.len() != k - 1 {
+        return false;
+    }
+
+    bytes.insert(0, 0x00);
+    unpad_pkcs1_5(&bytes, 0x01, true)
+        .filter(|d| d.starts_with(&hash.digest_info(msg)))
+        .is_some()
+}
@@ -0,0 +1,84 @@
+use crate::shared::rsa::Hash;
+use num_bigint::BigUint;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    // The number of bits the modulus needs for the forgery to fit, and the number of bits it has.
+    ModulusTooSmall(u64, u64),
+    // The forgery would need a larger modulus than anyone uses.
+    ExponentTooLarge,
+}
+
+const MAX_MODULUS_BITS: u64 = 16384;
+
+// The smallest s with s^e >= x.
+fn nth_root_ceil(x: &BigUint, e: u32) -> BigUint {
+    let root = x.nth_root(e);
+    if root.pow(e) < *x {
+        root + 1u8
+    } else {
+        root
+    }
+}
+
+fn prefix(digest_info: &[u8]) -> Vec<u8> {
+    let mut prefix = vec![0x00, 0x01, 0xFF, 0x00];
+    prefix.extend_from_slice(digest_info);
+    prefix
+}
+
+// Returns a signature whose e-th power is 00 01 FF 00 || DigestInfo || garbage and stays below n.
+fn forge_for_length(e: u32, digest_info: &[u8], n: &BigUint) -> Option<BigUint> {
+    let k = usize::try_from(n.bits().div_ceil(8)).unwrap();
+    let prefix = prefix(digest_info);
+    let garbage_bits = 8 * u64::try_from(k.checked_sub(prefix.len())?).unwrap();
+    let low = BigUint::from_bytes_be(&prefix) << garbage_bits;
+    let high = &low + ((BigUint::from(1u8) << garbage_bits) - 1u8);
+    // This fails when the e-th powers of consecutive integers around low are further apart than the garbage allows.
+    let s = nth_root_ceil(&low, e);
+    let power = s.pow(e);
+    if power <= high && power < *n {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+// A modulus length in bytes for which the forgery always fits.
+// The e-th powers of consecutive integers around low differ by less than e * s^(e - 1), with s < 2^(bits(low) / e + 1),
+// so g garbage bits are enough once g > (e - 1) * bits(prefix) + e * (log2(e) + 1 + e).
+fn sufficient_length(e: u32, digest_info: &[u8]) -> u64 {
+    let prefix = prefix(digest_info);
+    let prefix_bits = BigUint::from_bytes_be(&prefix).bits();
+    let e = u64::from(e);
+    // This saturates instead of overflowing for huge e, which is far beyond any usable modulus anyway.
+    let garbage_bits = ((e - 1) * prefix_bits)
+        .saturating_add(e.saturating_mul(u64::from(e.ilog2()) + 1 + e))
+        .saturating_add(1);
+    garbage_bits
+        .div_ceil(8)
+        .saturating_add(u64::try_from(prefix.len()).unwrap())
+}
+
+// Bleichenbacher's forgery against verifiers that don't check what follows the DigestInfo.
+// The forgery only depends on n through its length, and works for any small e.
+pub fn forge(public_key: &(BigUint, BigUint), msg: &[u8], hash: Hash) -> Result<Vec<u8>, Error> {
+    let (n, e) = public_key;
+    let e = u32::try_from(e).map_err(|_| Error::ExponentTooLarge)?;
+    let k = usize::try_from(n.bits().div_ceil(8)).unwrap();
+    let digest_info = &hash.digest_info(msg);
+    if let Some(s) = forge_for_length(e, digest_info, n) {
+        return Ok(s.to_bytes_be());
+    }
+
+    let sufficient = sufficient_length(e, digest_info);
+    if sufficient > MAX_MODULUS_BITS / 8 {
+        return Err(Error::ExponentTooLarge);
+    }
+
+    let required = (k + 1..=usize::try_from(sufficient).unwrap())
+        // The smallest k byte modulus is the hardest to stay below.
+        .find(|&k| forge_for_length(e, digest_info, &(BigUint::from(1u8) << (8 * k - 8))).is_some())
+        .map_or(sufficient, |k| u64::try_from(k).unwrap());
+    Err(Error::ModulusTooSmall(8 * required, n.bits()))
+}
@@ -0,0 +1,58 @@
+use crate::shared::continued_fraction::{convergents, expansion};
+use crate::shared::rsa::PrivateKey;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::Zero;
+
+// Recovers p and q from n and p + q, if they're consistent.
+pub(crate) fn factor_from_sum(n: &BigUint, sum: &BigUint) -> Option<(BigUint, BigUint)> {
+    // p and q are the roots of x^2 - sum * x + n.
+    let square = sum * sum;
+    let four_n = n * 4u8;
+    if square < four_n {
+        return None;
+    }
+
+    let discriminant = square - four_n;
+    let root = discriminant.sqrt();
+    if &root * &root != discriminant || sum < &root {
+        return None;
+    }
+
+    let p = (sum + &root) / 2u8;
+    let q = (sum - &root) / 2u8;
+    if q.is_zero() || &p * &q != *n {
+        return None;
+    }
+
+    Some((p, q))
+}
+
+// Checks a guess for d and the k in e * d = 1 + k * phi(n) by factoring n with it.
+pub(crate) fn private_key_from_guess(
+    public_key: &(BigUint, BigUint),
+    k: &BigUint,
+    d: &BigUint,
+) -> Option<PrivateKey> {
+    let (n, e) = public_key;
+    if k.is_zero() || d.is_zero() {
+        return None;
+    }
+
+    let (phi, r) = (e * d - 1u8).div_rem(k);
+    if !r.is_zero() || phi > *n {
+        return None;
+    }
+
+    let (p, q) = factor_from_sum(n, &(n + 1u8 - phi))?;
+    PrivateKey::from_primes(&p, &q, e, d)
+}
+
+// Works when d < n^(1/4) / 3: then k / d is a convergent of e / n, where e * d = 1 + k * phi(n).
+#[must_use]
+pub fn attack(public_key: &(BigUint, BigUint)) -> Option<PrivateKey> {
+    let (n, e) = public_key;
+    convergents(&expansion(e, n))
+        .into_iter()
+        .find_map(|(k, d)| private_key_from_guess(public_key, &k, &d))
+}
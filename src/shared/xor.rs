@@ -1,11 +1,15 @@
+use std::collections::{HashMap, HashSet};
 use std::ops::Add;
 
+const ENGLISH: &[u8] = include_bytes!("english.txt");
 #[allow(clippy::approx_constant)]
-const LETTER_FREQUENCIES: [f64; 26] = [
+pub const LETTER_FREQUENCIES: [f64; 26] = [
     8.12, 1.49, 2.71, 4.32, 12.02, 2.30, 2.03, 5.92, 7.31, 0.10, 0.69, 3.98, 2.61, 6.95, 7.68,
     1.82, 0.11, 6.02, 6.28, 9.10, 2.88, 1.11, 2.09, 0.17, 2.11, 0.07,
 ];
 const N: f64 = 182_303.0;
+// Comparing every pair of blocks is quadratic, and a handful of blocks is enough to tell key sizes apart.
+const MAX_DISTANCE_BLOCKS: usize = 8;
 
 pub fn xor(a: &mut [u8], b: &[u8]) {
     assert_eq!(a.len(), b.len());
@@ -24,6 +28,12 @@ pub fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
         .fold(0, u32::add)
 }
 
+// The log frequency given to non-letters, as if they were 100 times rarer than a single occurrence in the sample.
+#[must_use]
+pub fn english_floor() -> f64 {
+    (0.01 / N).log10()
+}
+
 #[must_use]
 pub fn score(pt: &[u8], floor: f64) -> Option<f64> {
     // ASCII graphic and whitespace only.
@@ -46,40 +56,384 @@ pub fn score(pt: &[u8], floor: f64) -> Option<f64> {
     }))
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonPrintable {
+    Reject,
+    // Subtracted from the score for every byte that is not ASCII graphic or whitespace.
+    Penalize(f64),
+}
+
+fn is_printable(b: u8) -> bool {
+    b.is_ascii_graphic() || b.is_ascii_whitespace()
+}
+
+fn non_printable_penalty(pt: &[u8], non_printable: NonPrintable) -> Option<f64> {
+    let count = pt.iter().filter(|&&b| !is_printable(b)).count();
+    match non_printable {
+        _ if count == 0 => Some(0.0),
+        NonPrintable::Reject => None,
+        NonPrintable::Penalize(penalty) => Some(penalty * f64::from(u32::try_from(count).unwrap())),
+    }
+}
+
+pub trait Scorer {
+    // Higher scores are more likely to be plaintext, None rejects the plaintext outright.
+    fn score(&self, pt: &[u8]) -> Option<f64>;
+}
+
+impl<F: Fn(&[u8]) -> Option<f64>> Scorer for F {
+    fn score(&self, pt: &[u8]) -> Option<f64> {
+        self(pt)
+    }
+}
+
+pub struct LetterFrequency {
+    floor: f64,
+    non_printable: NonPrintable,
+}
+
+impl LetterFrequency {
+    #[must_use]
+    pub fn new(floor: f64, non_printable: NonPrintable) -> Self {
+        Self {
+            floor,
+            non_printable,
+        }
+    }
+
+    #[must_use]
+    pub fn new_english() -> Self {
+        Self::new(english_floor(), NonPrintable::Reject)
+    }
+}
+
+impl Scorer for LetterFrequency {
+    fn score(&self, pt: &[u8]) -> Option<f64> {
+        let penalty = non_printable_penalty(pt, self.non_printable)?;
+        let printable = pt
+            .iter()
+            .copied()
+            .filter(|&b| is_printable(b))
+            .collect::<Vec<u8>>();
+        score(&printable, self.floor).map(|score| score - penalty)
+    }
+}
+
+pub struct ChiSquared {
+    expected: [f64; 28],
+    non_printable: NonPrintable,
+}
+
+impl ChiSquared {
+    // Expected frequencies for the 26 letters (case-insensitive), spaces, and all other printable bytes.
+    #[must_use]
+    pub fn new(expected: [f64; 28], non_printable: NonPrintable) -> Self {
+        let total = expected.iter().sum::<f64>();
+        Self {
+            expected: expected.map(|e| e / total),
+            non_printable,
+        }
+    }
+
+    #[must_use]
+    pub fn new_english() -> Self {
+        let mut expected = [0.0; 28];
+        LETTER_FREQUENCIES
+            .iter()
+            .enumerate()
+            .for_each(|(i, f)| expected[i] = f * 0.8);
+        expected[26] = 15.0;
+        expected[27] = 5.0;
+        Self::new(expected, NonPrintable::Reject)
+    }
+}
+
+impl Scorer for ChiSquared {
+    fn score(&self, pt: &[u8]) -> Option<f64> {
+        let penalty = non_printable_penalty(pt, self.non_printable)?;
+        let mut observed = [0; 28];
+        pt.iter().filter(|&&b| is_printable(b)).for_each(|&b| {
+            if b.is_ascii_alphabetic() {
+                observed[usize::from(b.to_ascii_lowercase() - b'a')] += 1;
+            } else if b == b' ' {
+                observed[26] += 1;
+            } else {
+                observed[27] += 1;
+            }
+        });
+        let total = f64::from(observed.iter().sum::<u32>());
+        if total == 0.0 {
+            return None;
+        }
+
+        let chi_squared = observed
+            .iter()
+            .zip(self.expected)
+            .filter(|&(_, e)| e > 0.0)
+            .map(|(&o, e)| {
+                let e = e * total;
+                (f64::from(o) - e).powi(2) / e
+            })
+            .sum::<f64>();
+        Some(-chi_squared - penalty)
+    }
+}
+
+// Lowercases letters, collapses whitespace into single spaces, and drops non-printable bytes.
+fn normalize(bytes: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(bytes.len());
+    bytes.iter().filter(|&&b| is_printable(b)).for_each(|&b| {
+        if !b.is_ascii_whitespace() {
+            normalized.push(b.to_ascii_lowercase());
+        } else if normalized.last() != Some(&b' ') {
+            normalized.push(b' ');
+        }
+    });
+    normalized
+}
+
+// N-grams only make sense for contiguous plaintext, so don't use this to score transposed columns.
+pub struct NGram {
+    n: usize,
+    log_probabilities: HashMap<Vec<u8>, f64>,
+    floor: f64,
+    non_printable: NonPrintable,
+}
+
+impl NGram {
+    #[must_use]
+    pub fn from_corpus(corpus: &[u8], n: usize, non_printable: NonPrintable) -> Self {
+        assert!(n > 0);
+        let corpus = normalize(corpus);
+        let mut counts = HashMap::new();
+        corpus
+            .windows(n)
+            .for_each(|ngram| *counts.entry(ngram.to_owned()).or_insert(0) += 1);
+        let total = f64::from(u32::try_from(corpus.len().saturating_sub(n - 1)).unwrap());
+        Self {
+            n,
+            log_probabilities: counts
+                .into_iter()
+                .map(|(ngram, count)| (ngram, (f64::from(count) / total).log10()))
+                .collect(),
+            floor: (0.01 / total).log10(),
+            non_printable,
+        }
+    }
+
+    #[must_use]
+    pub fn new_english_bigrams() -> Self {
+        Self::from_corpus(ENGLISH, 2, NonPrintable::Reject)
+    }
+
+    #[must_use]
+    pub fn new_english_quadgrams() -> Self {
+        Self::from_corpus(ENGLISH, 4, NonPrintable::Reject)
+    }
+
+    #[must_use]
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    #[must_use]
+    pub fn log_probability(&self, ngram: &[u8]) -> f64 {
+        *self.log_probabilities.get(ngram).unwrap_or(&self.floor)
+    }
+
+    // For ciphertexts that were stripped of everything but letters.
+    #[must_use]
+    pub fn new_english_letter_quadgrams() -> Self {
+        let letters = ENGLISH
+            .iter()
+            .copied()
+            .filter(u8::is_ascii_alphabetic)
+            .collect::<Vec<u8>>();
+        Self::from_corpus(&letters, 4, NonPrintable::Reject)
+    }
+}
+
+impl Scorer for NGram {
+    fn score(&self, pt: &[u8]) -> Option<f64> {
+        let penalty = non_printable_penalty(pt, self.non_printable)?;
+        let score = normalize(pt)
+            .windows(self.n)
+            .map(|ngram| self.log_probability(ngram))
+            .sum::<f64>();
+        Some(score - penalty)
+    }
+}
+
+// Scores raw bytes against a reference distribution, so it never rejects binary plaintexts.
+pub struct ByteHistogram {
+    log_probabilities: [f64; 256],
+}
+
+impl ByteHistogram {
+    #[must_use]
+    pub fn from_corpus(corpus: &[u8]) -> Self {
+        let mut counts = [0; 256];
+        corpus.iter().for_each(|&b| counts[usize::from(b)] += 1);
+        let total = f64::from(u32::try_from(corpus.len()).unwrap());
+        let floor = (0.01 / total).log10();
+        Self {
+            log_probabilities: counts.map(|count: u32| {
+                if count == 0 {
+                    floor
+                } else {
+                    (f64::from(count) / total).log10()
+                }
+            }),
+        }
+    }
+
+    #[must_use]
+    pub fn new_english() -> Self {
+        Self::from_corpus(ENGLISH)
+    }
+}
+
+impl Scorer for ByteHistogram {
+    fn score(&self, pt: &[u8]) -> Option<f64> {
+        Some(
+            pt.iter()
+                .map(|&b| self.log_probabilities[usize::from(b)])
+                .sum(),
+        )
+    }
+}
+
+// Returns at most n candidates, best first.
 #[must_use]
-pub fn frequency_analysis(ct: &[u8]) -> Option<(f64, u8, Vec<u8>)> {
-    let floor = (0.01 / N).log10();
-    (0..=255)
+pub fn frequency_analysis_ranked<S: Scorer + ?Sized>(
+    ct: &[u8],
+    scorer: &S,
+    n: usize,
+) -> Vec<(f64, u8, Vec<u8>)> {
+    let mut candidates = (0..=255)
         .map(|key| {
             let mut pt = ct.to_owned();
             xor_with_key(&mut pt, &[key]);
             (key, pt)
         })
-        .filter_map(|(key, pt)| score(&pt, floor).map(|score| (score, key, pt)))
-        .max_by(|(a, _, _), (b, _, _)| a.partial_cmp(b).unwrap())
+        .filter_map(|(key, pt)| scorer.score(&pt).map(|score| (score, key, pt)))
+        .collect::<Vec<(f64, u8, Vec<u8>)>>();
+    candidates.sort_by(|(a, _, _), (b, _, _)| b.total_cmp(a));
+    candidates.truncate(n);
+    candidates
 }
 
 #[must_use]
-pub fn guess_key_sizes(ct: &[u8], max_key_size: usize) -> Vec<usize> {
-    let mut key_sizes = (2..=max_key_size)
-        .map(|key_size| {
-            let mut distance = 0;
-            let mut i = 0;
-            while (i + 2) * key_size < ct.len() {
-                distance += hamming_distance(
-                    &ct[i * key_size..(i + 1) * key_size],
-                    &ct[(i + 1) * key_size..(i + 2) * key_size],
-                );
-                i += 2;
-            }
+pub fn frequency_analysis<S: Scorer + ?Sized>(ct: &[u8], scorer: &S) -> Option<(f64, u8, Vec<u8>)> {
+    frequency_analysis_ranked(ct, scorer, 1).pop()
+}
+
+// Averages the Hamming distance over all pairs of the first few whole blocks, normalized by the key size.
+fn normalized_distance(ct: &[u8], key_size: usize) -> f64 {
+    let blocks = ct
+        .chunks_exact(key_size)
+        .take(MAX_DISTANCE_BLOCKS)
+        .collect::<Vec<&[u8]>>();
+    let mut distance = 0;
+    let mut pairs = 0;
+    (0..blocks.len()).for_each(|i| {
+        (i + 1..blocks.len()).for_each(|j| {
+            distance += hamming_distance(blocks[i], blocks[j]);
+            pairs += 1;
+        });
+    });
+
+    f64::from(distance) / (f64::from(pairs) * f64::from(u32::try_from(key_size).unwrap()))
+}
+
+// Returns (key size, normalized Hamming distance) pairs, most likely key size first.
+// Key sizes for which the ciphertext doesn't contain at least two whole blocks are skipped.
+#[must_use]
+pub fn estimate_key_sizes(ct: &[u8], max_key_size: usize) -> Vec<(usize, f64)> {
+    let mut key_sizes = (2..=max_key_size.min(ct.len() / 2))
+        .map(|key_size| (key_size, normalized_distance(ct, key_size)))
+        .collect::<Vec<(usize, f64)>>();
+    key_sizes.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    key_sizes
+}
+
+#[must_use]
+pub fn index_of_coincidence(bytes: &[u8]) -> f64 {
+    if bytes.len() < 2 {
+        return 0.0;
+    }
+
+    let mut counts = [0u32; 256];
+    bytes.iter().for_each(|&b| counts[usize::from(b)] += 1);
+    let n = f64::from(u32::try_from(bytes.len()).unwrap());
+    counts
+        .iter()
+        .map(|&c| f64::from(c) * (f64::from(c) - 1.0))
+        .sum::<f64>()
+        / (n * (n - 1.0))
+}
+
+#[must_use]
+pub fn english_index_of_coincidence() -> f64 {
+    index_of_coincidence(ENGLISH)
+}
 
-            let normalized_distance =
-                f64::from(distance) / f64::from(u32::try_from(i / 2 * key_size).unwrap());
-            (key_size, normalized_distance)
+// Returns (key size, average index of coincidence of the columns) pairs, most likely key size first.
+#[must_use]
+pub fn estimate_key_sizes_ioc(ct: &[u8], max_key_size: usize) -> Vec<(usize, f64)> {
+    let mut key_sizes = (1..=max_key_size.min(ct.len() / 2))
+        .map(|key_size| {
+            let ioc = (0..key_size)
+                .map(|offset| index_of_coincidence(&transpose(ct, key_size, offset)))
+                .sum::<f64>()
+                / f64::from(u32::try_from(key_size).unwrap());
+            (key_size, ioc)
         })
         .collect::<Vec<(usize, f64)>>();
-    key_sizes.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    key_sizes.sort_by(|(_, a), (_, b)| b.total_cmp(a));
     key_sizes
+}
+
+// Friedman's estimate of the key size, given the index of coincidence of the plaintext language and of random bytes.
+#[must_use]
+pub fn friedman_test(ct: &[u8], pt_ioc: f64, random_ioc: f64) -> Option<f64> {
+    let ct_ioc = index_of_coincidence(ct);
+    if ct.len() < 2 || ct_ioc <= random_ioc {
+        return None;
+    }
+
+    Some((pt_ioc - random_ioc) / (ct_ioc - random_ioc))
+}
+
+// Returns (shift, fraction of equal bytes) pairs, most likely key size first.
+#[must_use]
+pub fn autocorrelation(ct: &[u8], max_shift: usize) -> Vec<(usize, f64)> {
+    let mut shifts = (1..=max_shift.min(ct.len().saturating_sub(1)))
+        .map(|shift| {
+            let coincidences = ct.iter().zip(&ct[shift..]).filter(|(a, b)| a == b).count();
+            (
+                shift,
+                f64::from(u32::try_from(coincidences).unwrap())
+                    / f64::from(u32::try_from(ct.len() - shift).unwrap()),
+            )
+        })
+        .collect::<Vec<(usize, f64)>>();
+    shifts.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    shifts
+}
+
+// The smallest period the bytes repeat with, which reveals a key that was recovered using a multiple of its size.
+#[must_use]
+pub fn shortest_period(bytes: &[u8]) -> usize {
+    (1..bytes.len())
+        .filter(|period| bytes.len().is_multiple_of(*period))
+        .find(|&period| (period..bytes.len()).all(|i| bytes[i] == bytes[i - period]))
+        .unwrap_or(bytes.len())
+}
+
+#[must_use]
+pub fn guess_key_sizes(ct: &[u8], max_key_size: usize) -> Vec<usize> {
+    estimate_key_sizes(ct, max_key_size)
         .into_iter()
         .map(|(key_size, _)| key_size)
         .collect()
@@ -96,19 +450,76 @@ pub fn transpose(ct: &[u8], key_size: usize, offset: usize) -> Vec<u8> {
     transposed
 }
 
+#[derive(Clone, Debug)]
+pub struct KeyCandidate {
+    pub key_size: usize,
+    pub normalized_distance: f64,
+    pub key: Vec<u8>,
+    pub score: f64,
+    // The best (score, key byte) pairs for every byte of the key, best first.
+    pub alternatives: Vec<Vec<(f64, u8)>>,
+}
+
+// Returns at most max_candidates key sizes for which every column can be decrypted, in key size estimate order.
 #[must_use]
-pub fn break_xor_with_key(ct: &[u8], max_key_size: usize) -> Option<Vec<u8>> {
-    guess_key_sizes(ct, max_key_size)
+pub fn break_xor_with_key_ranked<S: Scorer + ?Sized>(
+    ct: &[u8],
+    max_key_size: usize,
+    scorer: &S,
+    max_candidates: usize,
+    max_alternatives: usize,
+) -> Vec<KeyCandidate> {
+    let mut key_sizes = HashSet::new();
+    estimate_key_sizes(ct, max_key_size)
         .into_iter()
-        .find_map(|key_size| {
+        .filter_map(|(key_size, mut distance)| {
             let mut key = Vec::with_capacity(key_size);
+            let mut score = 0.0;
+            let mut alternatives = Vec::with_capacity(key_size);
             for offset in 0..key_size {
                 let transposed = transpose(ct, key_size, offset);
-                match frequency_analysis(&transposed) {
-                    Some((_, k, _)) => key.push(k),
-                    None => return None,
-                }
+                let candidates =
+                    frequency_analysis_ranked(&transposed, scorer, max_alternatives.max(1));
+                let (s, k, _) = candidates.first()?;
+                key.push(*k);
+                score += s;
+                alternatives.push(
+                    candidates
+                        .into_iter()
+                        .take(max_alternatives)
+                        .map(|(s, k, _)| (s, k))
+                        .collect(),
+                );
             }
-            Some(key)
+            // If the key repeats itself, we guessed a multiple of the real key size.
+            let period = shortest_period(&key);
+            if period < key_size {
+                key.truncate(period);
+                alternatives.truncate(period);
+                score *= f64::from(u32::try_from(period).unwrap())
+                    / f64::from(u32::try_from(key_size).unwrap());
+                distance = normalized_distance(ct, period);
+            }
+            Some(KeyCandidate {
+                key_size: period,
+                normalized_distance: distance,
+                key,
+                score,
+                alternatives,
+            })
         })
+        .filter(|candidate| key_sizes.insert(candidate.key_size))
+        .take(max_candidates)
+        .collect()
+}
+
+#[must_use]
+pub fn break_xor_with_key<S: Scorer + ?Sized>(
+    ct: &[u8],
+    max_key_size: usize,
+    scorer: &S,
+) -> Option<Vec<u8>> {
+    break_xor_with_key_ranked(ct, max_key_size, scorer, 1, 1)
+        .pop()
+        .map(|candidate| candidate.key)
 }